@@ -0,0 +1,10 @@
+#version 450
+
+layout(location = 0) out vec2 frag_uv;
+
+void main() 
+{
+    // One triangle that covers the screen: (0,0), (2,0) and (0,2) in uv.
+    frag_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(frag_uv * 2.0 - 1.0, 0.0, 1.0);
+}
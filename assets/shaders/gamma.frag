@@ -0,0 +1,28 @@
+#version 450
+
+layout(binding = 0) uniform sampler2D input_image;
+
+layout(push_constant) uniform PushConstants
+{
+    float exposure;
+    uint tonemap_operator;
+    float gamma;
+    uint srgb_target;
+} pcs;
+
+layout(location = 0) in vec2 frag_uv;
+
+layout(location = 0) out vec4 out_colour;
+
+void main() 
+{
+    vec3 colour = texture(input_image, frag_uv).rgb;
+
+    // An sRGB swapchain encodes on write.
+    if (pcs.srgb_target == 0)
+    {
+        colour = pow(max(colour, vec3(0.0)), vec3(1.0 / pcs.gamma));
+    }
+
+    out_colour = vec4(colour, 1.0);
+}
@@ -0,0 +1,14 @@
+#version 450
+
+layout(binding = 0) uniform sampler2D font_atlas;
+
+layout(location = 0) in vec2 frag_tex_coord;
+layout(location = 1) in vec4 frag_colour;
+
+layout(location = 0) out vec4 out_colour;
+
+void main() 
+{
+    float coverage = texture(font_atlas, frag_tex_coord).r;
+    out_colour = vec4(frag_colour.rgb, frag_colour.a * coverage);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(push_constant) uniform PushConstants
+{
+    vec2 screen_size;
+} pcs;
+
+layout(location = 0) in vec2 in_position;
+layout(location = 1) in vec2 in_tex_coord;
+layout(location = 2) in vec4 in_colour;
+
+layout(location = 0) out vec2 frag_tex_coord;
+layout(location = 1) out vec4 frag_colour;
+
+void main() 
+{
+    // Positions are in pixels from the top left corner.
+    gl_Position = vec4(in_position / pcs.screen_size * 2.0 - 1.0, 0.0, 1.0);
+    frag_tex_coord = in_tex_coord;
+    frag_colour = in_colour;
+}
@@ -0,0 +1,15 @@
+#version 450
+
+layout(push_constant) uniform PushConstants
+{
+    mat4 model;
+    uint id;
+} pcs;
+
+// Model index + 1, so the cleared 0 means nothing was hit.
+layout(location = 0) out uint out_id;
+
+void main() 
+{
+    out_id = pcs.id;
+}
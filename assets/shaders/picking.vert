@@ -0,0 +1,22 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject
+{
+    mat4 view;
+    mat4 proj;
+    mat4 light_view_proj;
+    vec4 light_direction;
+} ubo;
+
+layout(push_constant) uniform PushConstants
+{
+    mat4 model;
+    uint id;
+} pcs;
+
+layout(location = 0) in vec3 in_position;
+
+void main() 
+{
+    gl_Position = ubo.proj * ubo.view * pcs.model * vec4(in_position, 1.0);
+}
@@ -1,18 +1,148 @@
 #version 450
 
+#ifdef BINDLESS
+#extension GL_EXT_nonuniform_qualifier : require
+#endif
+
 layout(location = 0) in vec3 frag_colour;
 layout(location = 1) in vec2 frag_tex_coord;
+layout(location = 2) in vec3 frag_view_position;
+layout(location = 3) in vec4 frag_light_position;
 
 layout(location = 0) out vec4 out_colour;
 
-layout(binding = 1) uniform sampler2D tex_sampler;
+// Set per pipeline variant, see `ViewMode::shader_mode`.
+layout(constant_id = 0) const uint VIEW_MODE = 0;
+
+const uint VIEW_SHADED = 0;
+const uint VIEW_WIREFRAME = 1;
+const uint VIEW_NORMALS = 2;
+const uint VIEW_UV_CHECKER = 3;
+const uint VIEW_MIP_LEVEL = 4;
+const uint VIEW_DEPTH = 5;
+const uint VIEW_OVERDRAW = 6;
+
+// Clip planes of `projection_matrix`.
+layout(constant_id = 1) const float NEAR = 0.1;
+layout(constant_id = 2) const float FAR = 10.0;
+
+layout(binding = 0) uniform UniformBufferObject
+{
+    mat4 view;
+    mat4 proj;
+    mat4 light_view_proj;
+    vec4 light_direction;
+} ubo;
+
+layout(set = 0, binding = 1) uniform sampler2DShadow shadow_map;
+
+#ifdef BINDLESS
+layout(set = 1, binding = 0) uniform sampler2D textures[];
 
 layout(push_constant) uniform PushConstants
 {
-    layout(offset = 64) float opacity;
+    uint texture_index;
 } pcs;
+#else
+layout(set = 1, binding = 0) uniform sampler2D tex_sampler;
+#endif
+
+layout(set = 2, binding = 0) uniform ObjectData
+{
+    mat4 model;
+    float opacity;
+    float highlight;
+} object;
+
+const float AMBIENT = 0.3;
+const vec3 HIGHLIGHT_COLOUR = vec3(1.0, 0.6, 0.1);
+
+// 3x3 percentage-closer filtering; 1.0 is fully lit.
+float shadow_factor(vec4 light_position)
+{
+    vec3 coords = light_position.xyz / light_position.w;
+    vec2 uv = coords.xy * 0.5 + 0.5;
+
+    if (coords.z > 1.0)
+    {
+        return 1.0;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+    float lit = 0.0;
+    for (int x = -1; x <= 1; ++x)
+    {
+        for (int y = -1; y <= 1; ++y)
+        {
+            lit += texture(shadow_map, vec3(uv + vec2(x, y) * texel, coords.z));
+        }
+    }
+
+    return lit / 9.0;
+}
+
+// Blue for the full resolution mip through green to red for the smallest ones.
+vec3 mip_colour(float level)
+{
+    float t = clamp(level / 6.0, 0.0, 1.0);
+    return t < 0.5
+        ? mix(vec3(0.0, 0.2, 1.0), vec3(0.0, 1.0, 0.2), t * 2.0)
+        : mix(vec3(0.0, 1.0, 0.2), vec3(1.0, 0.1, 0.0), t * 2.0 - 1.0);
+}
 
 void main() 
 {
-    out_colour = vec4(texture(tex_sampler, frag_tex_coord).rgb, pcs.opacity);
-}
\ No newline at end of file
+    // Flat normal from the view space position, turned towards the camera.
+    vec3 normal = normalize(cross(dFdx(frag_view_position), dFdy(frag_view_position)));
+    if (dot(normal, frag_view_position) > 0.0)
+    {
+        normal = -normal;
+    }
+
+    vec3 light = normalize(mat3(ubo.view) * -ubo.light_direction.xyz);
+    float diffuse = max(dot(normal, light), 0.0) * shadow_factor(frag_light_position);
+
+    switch (VIEW_MODE)
+    {
+    case VIEW_WIREFRAME:
+        out_colour = vec4(0.9, 0.9, 0.9, 1.0);
+        return;
+    case VIEW_NORMALS:
+        out_colour = vec4(normal * 0.5 + 0.5, 1.0);
+        return;
+    case VIEW_UV_CHECKER:
+    {
+        ivec2 cell = ivec2(floor(frag_tex_coord * 16.0));
+        out_colour = vec4(vec3(((cell.x + cell.y) & 1) == 0 ? 0.9 : 0.2), 1.0);
+        return;
+    }
+    case VIEW_MIP_LEVEL:
+    {
+#ifdef BINDLESS
+        float level = textureQueryLod(textures[nonuniformEXT(pcs.texture_index)], frag_tex_coord).x;
+#else
+        float level = textureQueryLod(tex_sampler, frag_tex_coord).x;
+#endif
+        out_colour = vec4(mip_colour(level), 1.0);
+        return;
+    }
+    case VIEW_DEPTH:
+    {
+        float linear_depth = NEAR * FAR / (FAR - gl_FragCoord.z * (FAR - NEAR));
+        out_colour = vec4(vec3(1.0 - (linear_depth - NEAR) / (FAR - NEAR)), 1.0);
+        return;
+    }
+    case VIEW_OVERDRAW:
+        // Added up by the blend state, so hot spots are the most covered pixels.
+        out_colour = vec4(0.15, 0.06, 0.02, 1.0);
+        return;
+    }
+
+#ifdef BINDLESS
+    vec3 albedo = texture(textures[nonuniformEXT(pcs.texture_index)], frag_tex_coord).rgb;
+#else
+    vec3 albedo = texture(tex_sampler, frag_tex_coord).rgb;
+#endif
+    vec3 lit = albedo * (AMBIENT + (1.0 - AMBIENT) * diffuse);
+    out_colour = vec4(mix(lit, HIGHLIGHT_COLOUR, object.highlight * 0.5), object.opacity);
+}
@@ -4,12 +4,16 @@ layout(binding = 0) uniform UniformBufferObject
 {
     mat4 view;
     mat4 proj;
+    mat4 light_view_proj;
+    vec4 light_direction;
 } ubo;
 
-layout(push_constant) uniform PushConstants
+layout(set = 2, binding = 0) uniform ObjectData
 {
     mat4 model;
-} pcs;
+    float opacity;
+    float highlight;
+} object;
 
 layout(location = 0) in vec3 in_position;
 layout(location = 1) in vec3 in_color;
@@ -17,10 +21,17 @@ layout(location = 2) in vec2 in_tex_coord;
 
 layout(location = 0) out vec3 frag_colour;
 layout(location = 1) out vec2 frag_text_coord;
+layout(location = 2) out vec3 frag_view_position;
+layout(location = 3) out vec4 frag_light_position;
 
 void main() 
 {
-    gl_Position = ubo.proj * ubo.view * pcs.model * vec4(in_position, 1.0);
+    vec4 world_position = object.model * vec4(in_position, 1.0);
+    vec4 view_position = ubo.view * world_position;
+
+    gl_Position = ubo.proj * view_position;
     frag_colour = in_color;
     frag_text_coord = in_tex_coord;
+    frag_view_position = view_position.xyz;
+    frag_light_position = ubo.light_view_proj * world_position;
 }
@@ -0,0 +1,21 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject
+{
+    mat4 view;
+    mat4 proj;
+    mat4 light_view_proj;
+    vec4 light_direction;
+} ubo;
+
+layout(push_constant) uniform PushConstants
+{
+    mat4 model;
+} pcs;
+
+layout(location = 0) in vec3 in_position;
+
+void main() 
+{
+    gl_Position = ubo.light_view_proj * pcs.model * vec4(in_position, 1.0);
+}
@@ -0,0 +1,14 @@
+#version 450
+
+layout(location = 0) in vec3 frag_direction;
+
+layout(location = 0) out vec4 out_colour;
+
+layout(binding = 2) uniform samplerCube skybox;
+
+void main() 
+{
+    // The scene is z up, cube maps are y up (see `cube_to_world` in skybox.rs).
+    vec3 direction = vec3(frag_direction.x, frag_direction.z, -frag_direction.y);
+    out_colour = vec4(texture(skybox, direction).rgb, 1.0);
+}
@@ -0,0 +1,40 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject
+{
+    mat4 view;
+    mat4 proj;
+    mat4 light_view_proj;
+    vec4 light_direction;
+} ubo;
+
+layout(location = 0) out vec3 frag_direction;
+
+const vec3 CORNERS[8] = vec3[](
+    vec3(-1.0, -1.0, -1.0), vec3( 1.0, -1.0, -1.0),
+    vec3( 1.0,  1.0, -1.0), vec3(-1.0,  1.0, -1.0),
+    vec3(-1.0, -1.0,  1.0), vec3( 1.0, -1.0,  1.0),
+    vec3( 1.0,  1.0,  1.0), vec3(-1.0,  1.0,  1.0)
+);
+
+const int INDICES[36] = int[](
+    0, 1, 2, 2, 3, 0,
+    4, 6, 5, 6, 4, 7,
+    0, 4, 5, 5, 1, 0,
+    1, 5, 6, 6, 2, 1,
+    2, 6, 7, 7, 3, 2,
+    3, 7, 4, 4, 0, 3
+);
+
+void main() 
+{
+    vec3 position = CORNERS[INDICES[gl_VertexIndex]];
+
+    // Rotation only, so the sky stays put when the camera moves.
+    mat4 view = mat4(mat3(ubo.view));
+    vec4 clip = ubo.proj * view * vec4(position, 1.0);
+
+    // z = w puts the sky at depth 1.0.
+    gl_Position = clip.xyww;
+    frag_direction = position;
+}
@@ -0,0 +1,40 @@
+#version 450
+
+layout(binding = 0) uniform sampler2D input_image;
+
+layout(push_constant) uniform PushConstants
+{
+    float exposure;
+    uint tonemap_operator;
+    float gamma;
+    uint srgb_target;
+} pcs;
+
+layout(location = 0) in vec2 frag_uv;
+
+layout(location = 0) out vec4 out_colour;
+
+vec3 reinhard(vec3 colour)
+{
+    return colour / (colour + vec3(1.0));
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 colour)
+{
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((colour * (a * colour + b)) / (colour * (c * colour + d) + e), 0.0, 1.0);
+}
+
+void main() 
+{
+    vec3 colour = texture(input_image, frag_uv).rgb * pcs.exposure;
+
+    colour = pcs.tonemap_operator == 0 ? reinhard(colour) : aces(colour);
+
+    out_colour = vec4(colour, 1.0);
+}
@@ -5,4 +5,15 @@ git submodule update --init --recursive
 docker pull docker.io/shadahub/shaderc || docker build --rm -t shadahub/shaderc -f shaderc/Dockerfile shaderc
 
 docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc shader.frag -o frag.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc -DBINDLESS shader.frag -o frag_bindless.spv
 docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc shader.vert -o vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc shadow.vert -o shadow_vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc picking.vert -o picking_vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc picking.frag -o picking_frag.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc skybox.vert -o skybox_vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc skybox.frag -o skybox_frag.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc fullscreen.vert -o fullscreen_vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc tonemap.frag -o tonemap_frag.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc gamma.frag -o gamma_frag.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc hud.vert -o hud_vert.spv
+docker run --rm -u $(id -u):$(id -g) -v $PWD/assets/shaders:/code shadahub/shaderc glslc hud.frag -o hud_frag.spv
@@ -5,64 +5,129 @@ use vulkanalia::prelude::v1_0::*;
 pub struct AppData 
 {
     // Debug
+    pub validation_enabled: bool,
+    pub debug_printf_enabled: bool,
     pub messenger: vk::DebugUtilsMessengerEXT,
+    // Instance
+    pub api_version: u32,
     // Surface
     pub surface: vk::SurfaceKHR,
     // Physical Device / Logical Device
     pub physical_device: vk::PhysicalDevice,
+    pub device_name: String,
     pub msaa_samples: vk::SampleCountFlags,
+    pub rendering: super::rendering::RenderingPath,
+    pub descriptor_indexing: super::bindless::DescriptorIndexing,
+    pub fill_mode_non_solid: bool,
     // Queues
     pub graphics_queue: vk::Queue,
     pub present_queue: vk::Queue,
     // Swapchain
     pub swapchain_format: vk::Format,
     pub swapchain_extent: vk::Extent2D,
+    pub requested_present_mode: vk::PresentModeKHR,
+    pub present_mode: vk::PresentModeKHR,
+    pub present_modes: Vec<vk::PresentModeKHR>,
+    // Swapchain images can be copied out for recording.
+    pub swapchain_capture: bool,
     pub swapchain: vk::SwapchainKHR,
     pub swapchain_images: Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
     // Pipeline
     pub render_pass: vk::RenderPass, 
-    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    pub frame_set_layout: vk::DescriptorSetLayout,
+    pub material_set_layout: vk::DescriptorSetLayout,
+    pub object_set_layout: vk::DescriptorSetLayout,
     pub pipeline_layout: vk::PipelineLayout,
     pub pipeline: vk::Pipeline,
+    pub transparent_pipeline: vk::Pipeline,
+    pub view_mode_pipelines: Vec<(super::view_modes::ViewMode, vk::Pipeline)>,
     // Framebuffers
-    pub framebuffers: Vec<vk::Framebuffer>,
+    pub framebuffer: vk::Framebuffer,
     // Command Pool
     pub command_pool: vk::CommandPool,
     // Colour
     pub colour_image: vk::Image,
     pub colour_image_memory: vk::DeviceMemory,
     pub colour_image_view: vk::ImageView,
+    pub hdr_image: vk::Image,
+    pub hdr_image_memory: vk::DeviceMemory,
+    pub hdr_image_view: vk::ImageView,
     // Depth
     pub depth_image: vk::Image,
     pub depth_image_memory: vk::DeviceMemory,
     pub depth_image_view: vk::ImageView,
+    pub depth_format: vk::Format,
+    // Shadows
+    pub shadow_extent: vk::Extent2D,
+    pub shadow_render_pass: vk::RenderPass,
+    pub shadow_image: vk::Image,
+    pub shadow_image_memory: vk::DeviceMemory,
+    pub shadow_image_view: vk::ImageView,
+    pub shadow_format: vk::Format,
+    pub shadow_sampler: vk::Sampler,
+    pub shadow_framebuffer: vk::Framebuffer,
+    pub shadow_pipeline_layout: vk::PipelineLayout,
+    pub shadow_pipeline: vk::Pipeline,
+    // Picking
+    pub picking_render_pass: vk::RenderPass,
+    pub picking_image: vk::Image,
+    pub picking_image_memory: vk::DeviceMemory,
+    pub picking_image_view: vk::ImageView,
+    pub picking_depth_image: vk::Image,
+    pub picking_depth_image_memory: vk::DeviceMemory,
+    pub picking_depth_image_view: vk::ImageView,
+    pub picking_framebuffer: vk::Framebuffer,
+    pub picking_pipeline_layout: vk::PipelineLayout,
+    pub picking_pipeline: vk::Pipeline,
+    // Skybox
+    pub skybox_image: vk::Image,
+    pub skybox_image_memory: vk::DeviceMemory,
+    pub skybox_image_view: vk::ImageView,
+    pub skybox_sampler: vk::Sampler,
+    pub skybox_pipeline: vk::Pipeline,
+    // Post-processing
+    pub post_descriptor_set_layout: vk::DescriptorSetLayout,
+    pub post_pipeline_layout: vk::PipelineLayout,
+    pub post_sampler: vk::Sampler,
+    pub post_descriptor_pool: vk::DescriptorPool,
+    pub post_passes: Vec<super::postprocess::PostPass>,
+    // HUD
+    pub hud_atlas_image: vk::Image,
+    pub hud_atlas_image_memory: vk::DeviceMemory,
+    pub hud_atlas_image_view: vk::ImageView,
+    pub hud_sampler: vk::Sampler,
+    pub hud_descriptor_pool: vk::DescriptorPool,
+    pub hud_descriptor_set: vk::DescriptorSet,
+    pub hud_pipeline_layout: vk::PipelineLayout,
+    pub hud_pipeline: vk::Pipeline,
     // Texture
     pub mip_levels: u32,
     pub texture_image: vk::Image,
     pub texture_image_memory: vk::DeviceMemory,
     pub texture_image_view: vk::ImageView,
     pub texture_sampler: vk::Sampler,
+    pub texture_index: u32,
+    pub material_sets: Vec<vk::DescriptorSet>,
+    // Bindless
+    pub bindless_capacity: u32,
+    pub bindless_texture_count: u32,
+    pub bindless_set_layout: vk::DescriptorSetLayout,
+    pub bindless_descriptor_pool: vk::DescriptorPool,
+    pub bindless_descriptor_set: vk::DescriptorSet,
     // Model
     pub vertices: Vec<super::vertices::Vertex>,
     pub indices: Vec<u32>,
+    pub meshes: Vec<super::model::Mesh>,
+    pub model_bounds: super::culling::Bounds,
     // Buffers
     pub vertex_buffer: vk::Buffer,
     pub vertex_buffer_memory: vk::DeviceMemory,
     pub index_buffer: vk::Buffer,
     pub index_buffer_memory: vk::DeviceMemory,
-    pub uniform_buffers: Vec<vk::Buffer>,
-    pub uniform_buffers_memory: Vec<vk::DeviceMemory>,
+    pub uniform_ring: super::ring_buffer::RingBuffer,
     // Descriptor
-    pub descriptor_pool: vk::DescriptorPool,
-    pub descriptor_sets: Vec<vk::DescriptorSet>,
-    // Per framebuffer Command Pools
-    pub command_pools: Vec<vk::CommandPool>,
-    pub command_buffers: Vec<vk::CommandBuffer>,
-    pub secondary_command_buffers: Vec<Vec<vk::CommandBuffer>>,
-    // Synchronization
-    pub image_available_semaphores: Vec<vk::Semaphore>,
-    pub render_finished_semaphores: Vec<vk::Semaphore>,
-    pub in_flight_fences: Vec<vk::Fence>,
-    pub images_in_flight: Vec<vk::Fence>,
+    pub descriptor_allocator: super::descriptors::DescriptorAllocator,
+    // Frames in flight
+    pub frames: Vec<super::frame::FrameContext>,
 }
@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+
+use vulkanalia::vk::InstanceV1_1;
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::Config;
+
+use super::appdata::AppData;
+use super::debug_utils::set_object_name;
+use super::physical_device::supports_extension;
+use super::uniform_buffer::FRAME_SET_SAMPLERS;
+
+/// Upper bound on the texture array, lowered to the device limits.
+pub const MAX_BINDLESS_TEXTURES: u32 = 4096;
+
+/// Where descriptor indexing comes from, if we use it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DescriptorIndexing
+{
+    /// A material set per texture, bound per draw.
+    #[default]
+    Disabled,
+    /// Core in Vulkan 1.2.
+    Core,
+    /// `VK_EXT_descriptor_indexing`, which needs 1.1 for `VK_KHR_maintenance3`.
+    Extension,
+}
+
+impl DescriptorIndexing
+{
+    pub fn is_enabled(self) -> bool
+    {
+        self != Self::Disabled
+    }
+}
+
+/// Picks bindless textures when enabled in the config and the device has the
+/// features we rely on, and sizes the texture array from the device limits.
+pub unsafe fn select_descriptor_indexing(
+    instance: &Instance,
+    config: &Config,
+    data: &mut AppData,
+) -> Result<DescriptorIndexing>
+{
+    if !config.bindless
+    {
+        return Ok(DescriptorIndexing::Disabled);
+    }
+
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    let version = properties.api_version.min(data.api_version);
+
+    let indexing = if version >= vk::make_version(1, 2, 0)
+    {
+        DescriptorIndexing::Core
+    }
+    else if version >= vk::make_version(1, 1, 0)
+        && supports_extension(instance, data.physical_device, &vk::EXT_DESCRIPTOR_INDEXING_EXTENSION.name)?
+    {
+        DescriptorIndexing::Extension
+    }
+    else
+    {
+        info!("Descriptor indexing is not available, binding textures per frame.");
+        return Ok(DescriptorIndexing::Disabled);
+    };
+
+    let mut indexing_features = vk::PhysicalDeviceDescriptorIndexingFeatures::builder();
+    let mut features = vk::PhysicalDeviceFeatures2::builder()
+        .push_next(&mut indexing_features);
+    instance.get_physical_device_features2(data.physical_device, &mut features);
+
+    let supported = indexing_features.runtime_descriptor_array == vk::TRUE
+        && indexing_features.descriptor_binding_partially_bound == vk::TRUE
+        && indexing_features.descriptor_binding_sampled_image_update_after_bind == vk::TRUE
+        && indexing_features.shader_sampled_image_array_non_uniform_indexing == vk::TRUE;
+
+    if !supported
+    {
+        info!("Descriptor indexing features are not supported, binding textures per frame.");
+        return Ok(DescriptorIndexing::Disabled);
+    }
+
+    // Combined image samplers count against both the sampler and the sampled image limits.
+    let mut indexing_properties = vk::PhysicalDeviceDescriptorIndexingProperties::builder();
+    let mut properties = vk::PhysicalDeviceProperties2::builder()
+        .push_next(&mut indexing_properties);
+    instance.get_physical_device_properties2(data.physical_device, &mut properties);
+
+    // The limits cover every set in the pipeline layout, so leave room for set 0.
+    let limit = indexing_properties.max_per_stage_descriptor_update_after_bind_samplers
+        .min(indexing_properties.max_per_stage_descriptor_update_after_bind_sampled_images)
+        .min(indexing_properties.max_descriptor_set_update_after_bind_samplers)
+        .min(indexing_properties.max_descriptor_set_update_after_bind_sampled_images);
+    data.bindless_capacity = MAX_BINDLESS_TEXTURES.min(limit.saturating_sub(FRAME_SET_SAMPLERS));
+
+    if data.bindless_capacity == 0
+    {
+        info!("Descriptor indexing limits leave no texture slots, binding textures per frame.");
+        return Ok(DescriptorIndexing::Disabled);
+    }
+
+    info!("Using bindless textures ({:?}, {} slots).", indexing, data.bindless_capacity);
+    Ok(indexing)
+}
+
+/// Set 1 of the main pipeline layout: a partially bound array of every texture.
+pub unsafe fn create_bindless_set_layout(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    if !data.descriptor_indexing.is_enabled()
+    {
+        return Ok(());
+    }
+
+    let textures_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(data.bindless_capacity)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+    // Slots past the registered textures are never written, and new textures
+    // can be added while earlier frames using the set are still in flight.
+    let binding_flags = &[vk::DescriptorBindingFlags::PARTIALLY_BOUND | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND];
+    let mut flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
+        .binding_flags(binding_flags);
+
+    let bindings = &[textures_binding];
+    let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+        .bindings(bindings)
+        .push_next(&mut flags_info);
+
+    data.bindless_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+    set_object_name(instance, device, data.bindless_set_layout, "descriptor_set_layout:bindless")?;
+
+    Ok(())
+}
+
+/// The one texture set shared by every frame, since it only ever grows.
+pub unsafe fn create_bindless_set(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    if !data.descriptor_indexing.is_enabled()
+    {
+        return Ok(());
+    }
+
+    let textures_size = vk::DescriptorPoolSize::builder()
+        .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(data.bindless_capacity);
+
+    let pool_sizes = &[textures_size];
+    let create_info = vk::DescriptorPoolCreateInfo::builder()
+        .flags(vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+        .pool_sizes(pool_sizes)
+        .max_sets(1);
+
+    data.bindless_descriptor_pool = device.create_descriptor_pool(&create_info, None)?;
+    set_object_name(instance, device, data.bindless_descriptor_pool, "descriptor_pool:bindless")?;
+
+    let set_layouts = &[data.bindless_set_layout];
+    let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(data.bindless_descriptor_pool)
+        .set_layouts(set_layouts);
+
+    data.bindless_descriptor_set = device.allocate_descriptor_sets(&allocate_info)?[0];
+    set_object_name(instance, device, data.bindless_descriptor_set, "descriptor_set:bindless")?;
+
+    Ok(())
+}
+
+/// Writes a texture into the next free slot and returns the index for push constants.
+///
+/// Without descriptor indexing the index is into `material_sets` instead, and
+/// each draw binds the material set it needs.
+pub unsafe fn register_texture(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+    image_view: vk::ImageView,
+    sampler: vk::Sampler,
+) -> Result<u32>
+{
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(image_view)
+        .sampler(sampler);
+
+    let image_info = &[info];
+
+    if !data.descriptor_indexing.is_enabled()
+    {
+        let index = data.material_sets.len() as u32;
+        let material_set = data.descriptor_allocator.allocate(instance, device, data.material_set_layout)?;
+        set_object_name(instance, device, material_set, &format!("descriptor_set:material[{}]", index))?;
+
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(material_set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(image_info);
+
+        device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+        data.material_sets.push(material_set);
+
+        return Ok(index);
+    }
+
+    let index = data.bindless_texture_count;
+    if index >= data.bindless_capacity
+    {
+        return Err(anyhow!("Bindless texture array is full ({} slots).", data.bindless_capacity));
+    }
+
+    let write = vk::WriteDescriptorSet::builder()
+        .dst_set(data.bindless_descriptor_set)
+        .dst_binding(0)
+        .dst_array_element(index)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(image_info);
+
+    device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+    data.bindless_texture_count += 1;
+
+    Ok(index)
+}
+
+pub unsafe fn destroy_bindless(device: &Device, data: &AppData)
+{
+    device.destroy_descriptor_pool(data.bindless_descriptor_pool, None);
+    device.destroy_descriptor_set_layout(data.bindless_set_layout, None);
+}
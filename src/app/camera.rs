@@ -0,0 +1,71 @@
+use nalgebra_glm as glm;
+
+/// Radians of rotation per pixel of cursor movement.
+const ORBIT_SPEED: f32 = 0.005;
+/// Distance factor per wheel line.
+const ZOOM_STEP: f32 = 0.9;
+const MIN_DISTANCE: f32 = 2.0;
+// Stays inside the far plane of `projection_matrix`.
+const MAX_DISTANCE: f32 = 8.0;
+// Just short of straight up or down, where `look_at` has no defined up.
+const MAX_PITCH: f32 = 1.5;
+
+/// A camera orbiting the origin with +z up.
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitCamera
+{
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for OrbitCamera
+{
+    /// Looking at the origin from (6, 2, 2).
+    fn default() -> Self
+    {
+        let eye = glm::vec3(6.0f32, 2.0, 2.0);
+
+        Self
+        {
+            yaw: eye.y.atan2(eye.x),
+            pitch: (eye.z / eye.norm()).asin(),
+            distance: eye.norm(),
+        }
+    }
+}
+
+impl OrbitCamera
+{
+    /// Rotates around the origin by a cursor movement in pixels.
+    pub fn orbit(&mut self, dx: f32, dy: f32)
+    {
+        self.yaw -= dx * ORBIT_SPEED;
+        self.pitch = (self.pitch + dy * ORBIT_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves closer for positive wheel lines and away for negative ones.
+    pub fn zoom(&mut self, lines: f32)
+    {
+        self.distance = (self.distance * ZOOM_STEP.powf(lines)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    pub fn eye(&self) -> glm::Vec3
+    {
+        glm::vec3(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+        ) * self.distance
+    }
+
+    /// Camera transform, shared by the uniform buffer and the draw sorting.
+    pub fn view_matrix(&self) -> glm::Mat4
+    {
+        glm::look_at(
+            &self.eye(),
+            &glm::vec3(0.0, 0.0, 0.0),
+            &glm::vec3(0.0, 0.0, 1.0),
+        )
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::Instant;
+
+/// Step used for single steps while paused when there is no fixed step.
+const DEFAULT_STEP: f64 = 1.0 / 60.0;
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 16.0;
+
+/// Simulated time that drives the animation.
+///
+/// Advances by the real frame time, or by `fixed_step` per frame for
+/// reproducible runs, scaled by `speed`. A fixed `override_time` freezes it
+/// for tests and reference images.
+#[derive(Copy, Clone, Debug)]
+pub struct Clock
+{
+    time: f64,
+    last_tick: Instant,
+    pub paused: bool,
+    pub speed: f64,
+    pub fixed_step: Option<f64>,
+    override_time: Option<f64>,
+    step_requested: bool,
+}
+
+impl Clock
+{
+    pub fn new(fixed_step: Option<f64>, speed: f64, paused: bool) -> Self
+    {
+        let clamped = speed.clamp(MIN_SPEED, MAX_SPEED);
+        if clamped != speed
+        {
+            warn!("Time scale {} is outside [{}, {}], using {}.", speed, MIN_SPEED, MAX_SPEED, clamped);
+        }
+
+        Self
+        {
+            time: 0.0,
+            last_tick: Instant::now(),
+            paused,
+            speed: clamped,
+            fixed_step: fixed_step.filter(|s| *s > 0.0),
+            override_time: None,
+            step_requested: false,
+        }
+    }
+
+    /// A clock that always reads `time`.
+    pub fn fixed(time: f64) -> Self
+    {
+        Self { override_time: Some(time), ..Self::new(None, 1.0, true) }
+    }
+
+    /// Advances the clock once per frame.
+    pub fn tick(&mut self)
+    {
+        let now = Instant::now();
+        let real_step = (now - self.last_tick).as_secs_f64();
+        self.last_tick = now;
+
+        if self.override_time.is_some() || (self.paused && !self.step_requested)
+        {
+            return;
+        }
+
+        let step = match (self.fixed_step, self.step_requested)
+        {
+            (Some(step), _) => step,
+            (None, true) => DEFAULT_STEP,
+            (None, false) => real_step,
+        };
+
+        self.time += step * self.speed;
+        self.step_requested = false;
+    }
+
+    /// Seconds of simulated time.
+    pub fn time(&self) -> f32
+    {
+        self.override_time.unwrap_or(self.time) as f32
+    }
+
+    pub fn toggle_pause(&mut self)
+    {
+        self.paused = !self.paused;
+    }
+
+    /// Pauses and advances a single frame on the next tick.
+    pub fn step(&mut self)
+    {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    /// Multiplies the speed, e.g. by 2 or 0.5.
+    pub fn scale_speed(&mut self, factor: f64)
+    {
+        self.speed = (self.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn fixed_clock_ignores_ticks_and_controls()
+    {
+        let mut clock = Clock::fixed(1.5);
+        clock.toggle_pause();
+        clock.step();
+        clock.scale_speed(4.0);
+        clock.tick();
+
+        assert_eq!(clock.time(), 1.5);
+    }
+
+    #[test]
+    fn fixed_step_advances_by_step_times_speed()
+    {
+        let mut clock = Clock::new(Some(0.25), 2.0, false);
+        clock.tick();
+        clock.tick();
+
+        assert_eq!(clock.time(), 1.0);
+    }
+
+    #[test]
+    fn paused_clock_stands_still()
+    {
+        let mut clock = Clock::new(Some(0.25), 1.0, false);
+        clock.toggle_pause();
+        assert!(clock.paused);
+        clock.tick();
+        assert_eq!(clock.time(), 0.0);
+
+        clock.toggle_pause();
+        assert!(!clock.paused);
+        clock.tick();
+        assert_eq!(clock.time(), 0.25);
+    }
+
+    #[test]
+    fn step_advances_a_single_tick_while_paused()
+    {
+        let mut clock = Clock::new(None, 1.0, true);
+        clock.step();
+        clock.tick();
+        clock.tick();
+
+        assert!(clock.paused);
+        assert_eq!(clock.time(), DEFAULT_STEP as f32);
+
+        // With a fixed step, that step is used instead.
+        let mut clock = Clock::new(Some(0.5), 1.0, false);
+        clock.step();
+        clock.tick();
+        clock.tick();
+
+        assert!(clock.paused);
+        assert_eq!(clock.time(), 0.5);
+    }
+
+    #[test]
+    fn speed_is_clamped()
+    {
+        let mut clock = Clock::new(None, 1.0, false);
+        for _ in 0..10
+        {
+            clock.scale_speed(2.0);
+        }
+        assert_eq!(clock.speed, MAX_SPEED);
+
+        for _ in 0..20
+        {
+            clock.scale_speed(0.5);
+        }
+        assert_eq!(clock.speed, MIN_SPEED);
+
+        assert_eq!(Clock::new(None, 100.0, false).speed, MAX_SPEED);
+        assert_eq!(Clock::new(None, 0.0, false).speed, MIN_SPEED);
+    }
+}
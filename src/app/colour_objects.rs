@@ -1,6 +1,8 @@
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
+use super::debug_utils::set_object_name;
+use super::postprocess::HDR_FORMAT;
 use super::texture::{create_image, create_image_view};
 
 use anyhow::Result;
@@ -19,7 +21,7 @@ pub unsafe fn create_colour_objects(
         data.swapchain_extent.height,
         1,
         data.msaa_samples,
-        data.swapchain_format,
+        HDR_FORMAT,
         vk::ImageTiling::OPTIMAL,
         vk::ImageUsageFlags::COLOR_ATTACHMENT
             | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
@@ -32,10 +34,44 @@ pub unsafe fn create_colour_objects(
     data.colour_image_view = create_image_view(
         device,
         data.colour_image,
-        data.swapchain_format,
+        HDR_FORMAT,
         vk::ImageAspectFlags::COLOR,
         1,
     )?;
 
+    set_object_name(instance, device, data.colour_image, "colour[msaa]")?;
+    set_object_name(instance, device, data.colour_image_view, "colour_view[msaa]")?;
+
+    // Resolve target, read by the post-process chain.
+
+    let (hdr_image, hdr_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.swapchain_extent.width,
+        data.swapchain_extent.height,
+        1,
+        vk::SampleCountFlags::_1,
+        HDR_FORMAT,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::SAMPLED,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.hdr_image = hdr_image;
+    data.hdr_image_memory = hdr_image_memory;
+
+    data.hdr_image_view = create_image_view(
+        device,
+        data.hdr_image,
+        HDR_FORMAT,
+        vk::ImageAspectFlags::COLOR,
+        1,
+    )?;
+
+    set_object_name(instance, device, data.hdr_image, "hdr")?;
+    set_object_name(instance, device, data.hdr_image_view, "hdr_view")?;
+
     Ok(())
 }
\ No newline at end of file
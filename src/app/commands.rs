@@ -3,33 +3,37 @@ use vulkanalia::prelude::v1_0::*;
 use nalgebra_glm as glm;
 
 use super::appdata::AppData;
+use super::debug_utils::{begin_label, end_label, set_object_name, DRAW_LABEL_COLOUR, PASS_LABEL_COLOUR};
+use super::postprocess::{self, HDR_FORMAT};
 use super::queue_family_indices::QueueFamilyIndices;
+use super::recording;
+use super::culling::Frustum;
+use super::lod;
+use super::view_modes;
+use super::picking::{self, PickingConstants, PickRequest};
+use crate::input::Action;
+use super::rendering::{begin_rendering, depth_aspects, end_rendering, image_barrier};
+use super::model::Lod;
+use super::stats::FrameStats;
+use super::uniform_buffer::{projection_matrix, update_descriptor_sets, update_uniform_buffer, ObjectData};
 
 use anyhow::{Result, Ok};
 
 
-pub unsafe fn create_command_pools(
+pub unsafe fn create_global_command_pool(
     instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()> 
 {
-    // Global
     data.command_pool = create_command_pool(instance, device, data)?;
-
-    // Per-framebuffer
-    let num_images = data.swapchain_images.len();
-    for _ in 0..num_images
-    {
-        let command_pool = create_command_pool(instance, device, data)?;
-        data.command_pools.push(command_pool);
-    }
+    set_object_name(instance, device, data.command_pool, "command_pool:global")?;
 
     Ok(())
 }
 
 // Commmand pools for short-lived command buffers
-unsafe fn create_command_pool(
+pub unsafe fn create_command_pool(
     instance: &Instance,
     device: &Device,
     data: &mut AppData,
@@ -44,28 +48,6 @@ unsafe fn create_command_pool(
     Ok(device.create_command_pool(&create_info, None)?)
 }
 
-pub unsafe fn create_command_buffers(
-    device: &Device, 
-    data: &mut AppData
-) -> Result<()> 
-{
-    let num_images = data.swapchain_images.len();
-    for image_index in 0..num_images
-    {
-        let allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_pool(data.command_pools[image_index])
-            .level(vk::CommandBufferLevel::PRIMARY)
-            .command_buffer_count(1);
-
-        let command_buffer = device.allocate_command_buffers(&allocate_info)?[0];    
-        data.command_buffers.push(command_buffer);
-    }
-
-    data.secondary_command_buffers = vec![vec![]; data.swapchain_images.len()];
-    
-    Ok(())
-}
-
 pub unsafe fn update_command_buffer(
     app: &mut super::App,
     image_index: usize,
@@ -73,10 +55,14 @@ pub unsafe fn update_command_buffer(
 {
     // Reset
     
-    let command_pool = app.data.command_pools[image_index];
+    let command_pool = app.data.frames[app.frame].command_pool;
     app.device.reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())?;
 
-    let command_buffer = app.data.command_buffers[image_index];
+    let command_buffer = app.data.frames[app.frame].command_buffer;
+
+    // The fence also covers the pick this frame copied out last time.
+    picking::resolve_pick(app)?;
+    recording::resolve_capture(app, app.frame)?;
 
     // Record Commands
     
@@ -85,6 +71,24 @@ pub unsafe fn update_command_buffer(
 
     app.device.begin_command_buffer(command_buffer, &begin_info)?;
 
+    let models = (0..app.models).map(|i| model_matrix(app, i)).collect::<Vec<_>>();
+    let (opaque, transparent, stats) = draw_instances(app, &models);
+    app.stats = FrameStats { frame_time: app.stats.frame_time, ..stats };
+
+    // The ring region and descriptor sets from this frame's last use are free
+    // again now that its fence has signaled.
+    app.data.uniform_ring.begin_region(app.frame);
+    let frame_offset = update_uniform_buffer(&mut app.data, &app.camera)?;
+    let object_offsets = models
+        .iter()
+        .enumerate()
+        .map(|(i, model)| app.data.uniform_ring.push(&ObjectData { model: *model, opacity: model_opacity(i), highlight: model_highlight(app, i) }))
+        .collect::<Result<Vec<_>>>()?;
+    update_descriptor_sets(&app.instance, &app.device, &mut app.data, app.frame)?;
+
+    // Copies outside the camera frustum can still cast shadows into it.
+    update_shadow_pass(app, command_buffer, frame_offset, &models)?;
+
     let render_area = vk::Rect2D::builder()
         .offset(vk::Offset2D::default())
         .extent(app.data.swapchain_extent);
@@ -99,21 +103,86 @@ pub unsafe fn update_command_buffer(
         depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
     };
 
-    let clear_values = &[color_clear_value, depth_clear_value];
-    let info = vk::RenderPassBeginInfo::builder()
-        .render_pass(app.data.render_pass)
-        .framebuffer(app.data.framebuffers[image_index])
-        .render_area(render_area)
-        .clear_values(clear_values);
+    begin_label(&app.instance, command_buffer, "main pass", PASS_LABEL_COLOUR)?;
 
-    app.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
+    if app.data.rendering.is_dynamic()
+    {
+        begin_scene_rendering(app, command_buffer, *render_area, color_clear_value, depth_clear_value);
+    }
+    else
+    {
+        let clear_values = &[color_clear_value, depth_clear_value];
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(app.data.render_pass)
+            .framebuffer(app.data.framebuffer)
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        app.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
+    }
     
-    let secondary_command_buffers = (0..app.models)
-        .map(|i| update_secondary_command_buffer(app, image_index, i))
-        .collect::<Result<Vec<_>, _>>()?;
+    // Opaque models first, then the sky behind them, then the transparent
+    // models back to front so each one blends over everything behind it.
+    // Debug view modes draw every model with their own pipeline instead.
+    let view_mode_pipeline = view_modes::view_mode_pipeline(&app.data, app.view_mode);
+    let mut draws = vec![];
+    if app.view_mode.draws_shaded()
+    {
+        draws.extend(opaque.iter().map(|i| (i, app.data.pipeline)));
+        draws.extend(transparent.iter().map(|i| (i, app.data.transparent_pipeline)));
+    }
+    if let Some(pipeline) = view_mode_pipeline
+    {
+        draws.extend(opaque.iter().chain(&transparent).map(|i| (i, pipeline)));
+    }
+
+    let sky_slot = app.view_mode.draws_shaded().then_some(opaque.len());
+    let mut secondary_command_buffers = vec![];
+    for slot in 0..=draws.len()
+    {
+        if sky_slot == Some(slot)
+        {
+            secondary_command_buffers.push(update_skybox_command_buffer(app, frame_offset)?);
+        }
+
+        if let Some((instance, pipeline)) = draws.get(slot)
+        {
+            let offsets = [frame_offset, object_offsets[instance.model_index]];
+            secondary_command_buffers.push(update_secondary_command_buffer(app, slot, instance, offsets, *pipeline)?);
+        }
+    }
+
     app.device.cmd_execute_commands(command_buffer, &secondary_command_buffers[..]);
 
-    app.device.cmd_end_render_pass(command_buffer);
+    if app.data.rendering.is_dynamic()
+    {
+        end_rendering(&app.device, &app.data, command_buffer);
+
+        // The resolved scene is sampled by the first post-process pass.
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.hdr_image,
+            vk::ImageAspectFlags::COLOR,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        );
+    }
+    else
+    {
+        app.device.cmd_end_render_pass(command_buffer);
+    }
+
+    end_label(&app.instance, command_buffer);
+
+    update_picking_pass(app, command_buffer, frame_offset, opaque.iter().chain(&transparent))?;
+
+    postprocess::record_post_passes(app, image_index, command_buffer)?;
+    recording::record_capture(app, image_index, command_buffer)?;
 
     app.device.end_command_buffer(command_buffer)?;
 
@@ -122,93 +191,689 @@ pub unsafe fn update_command_buffer(
 
 unsafe fn update_secondary_command_buffer(
     app: &mut super::App,
-    image_index: usize,
-    model_index: usize,
+    slot: usize,
+    instance: &DrawInstance,
+    offsets: [u32; 2],
+    pipeline: vk::Pipeline,
 ) -> Result<vk::CommandBuffer>
 {
     // Allocate
 
-    let command_buffers = &mut app.data.secondary_command_buffers[image_index];
-    while model_index >= command_buffers.len()
+    let frame = &mut app.data.frames[app.frame];
+    while slot >= frame.secondary_command_buffers.len()
     {
         let allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_pool(app.data.command_pools[image_index])
+            .command_pool(frame.command_pool)
             .level(vk::CommandBufferLevel::SECONDARY)
             .command_buffer_count(1);
 
         let command_buffer = app.device.allocate_command_buffers(&allocate_info)?[0];
-        command_buffers.push(command_buffer)
+        let name = format!("secondary[{}][{}]", app.frame, frame.secondary_command_buffers.len());
+        set_object_name(&app.instance, &app.device, command_buffer, &name)?;
+        frame.secondary_command_buffers.push(command_buffer)
     }
 
-    let command_buffer = command_buffers[model_index];
+    let command_buffer = frame.secondary_command_buffers[slot];
 
-    // Model Matrix 
+    // Record Commands
+    
+    begin_secondary_command_buffer(app, command_buffer)?;
+    begin_label(&app.instance, command_buffer, &format!("model[{}]", instance.model_index), DRAW_LABEL_COLOUR)?;
+
+    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+
+    // Dynamic state is not inherited by secondary command buffers.
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(app.data.swapchain_extent.width as f32)
+        .height(app.data.swapchain_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+    app.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+    app.device.cmd_set_scissor(command_buffer, 0, &[vk::Rect2D { offset: vk::Offset2D::default(), extent: app.data.swapchain_extent }]);
+    app.device.cmd_set_line_width(command_buffer, 1.0);
+    app.device.cmd_bind_vertex_buffers(command_buffer, 0, &[app.data.vertex_buffer], &[0]);
+    app.device.cmd_bind_index_buffer(command_buffer, app.data.index_buffer, 0, vk::IndexType::UINT32);
+    app.device.cmd_bind_descriptor_sets(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        app.data.pipeline_layout,
+        0,
+        &[app.data.frames[app.frame].frame_set],
+        &offsets[..1],
+    );
+    app.device.cmd_bind_descriptor_sets(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        app.data.pipeline_layout,
+        2,
+        &[app.data.frames[app.frame].object_set],
+        &offsets[1..],
+    );
 
-    let y = (((model_index % 2) as f32) * 2.5) - 1.25;
-    let z = (((model_index / 2) as f32) * -2.0) + 1.0;
+    let bindless = app.data.descriptor_indexing.is_enabled();
+    if bindless
+    {
+        app.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            app.data.pipeline_layout,
+            1,
+            &[app.data.bindless_descriptor_set],
+            &[],
+        );
+    }
 
-    let model = glm::translate(
-        &glm::identity(), 
-        &glm::vec3(0.0, y, z),
-    );
+    // Bindless only changes the texture index between meshes, otherwise each
+    // mesh binds its material set.
+    for (lod, texture) in &instance.lods
+    {
+        if bindless
+        {
+            app.device.cmd_push_constants(
+                command_buffer,
+                app.data.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                &texture.to_ne_bytes()[..],
+            );
+        }
+        else
+        {
+            app.device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                app.data.pipeline_layout,
+                1,
+                &[app.data.material_sets[*texture as usize]],
+                &[],
+            );
+        }
+        app.device.cmd_draw_indexed(command_buffer, lod.index_count, 1, lod.first_index, 0, 0);
+    }
 
-    let time = app.start.elapsed().as_secs_f32();
+    end_label(&app.instance, command_buffer);
+    app.device.end_command_buffer(command_buffer)?;
 
-    let model = glm::rotate(
-        &model,
-        time * glm::radians(&glm::vec1(90.0))[0],
-        &glm::vec3(0.0, 0.0, 1.0));
+    Ok(command_buffer)
+}
 
-    let (_, model_bytes, _) = model.as_slice().align_to::<u8>();
+unsafe fn update_skybox_command_buffer(
+    app: &super::App,
+    frame_offset: u32,
+) -> Result<vk::CommandBuffer>
+{
+    let command_buffer = app.data.frames[app.frame].skybox_command_buffer;
 
-    // Opacity 
+    begin_secondary_command_buffer(app, command_buffer)?;
+    begin_label(&app.instance, command_buffer, "skybox", DRAW_LABEL_COLOUR)?;
 
-    let opacity = (model_index + 1) as f32 * 0.25;
-    let opacity_bytes = &opacity.to_ne_bytes()[..];
+    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, app.data.skybox_pipeline);
 
-    let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
-        .render_pass(app.data.render_pass)
-        .subpass(0)
-        .framebuffer(app.data.framebuffers[image_index]);
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(app.data.swapchain_extent.width as f32)
+        .height(app.data.swapchain_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+    app.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+    app.device.cmd_set_scissor(command_buffer, 0, &[vk::Rect2D { offset: vk::Offset2D::default(), extent: app.data.swapchain_extent }]);
 
-    let begin_info = vk::CommandBufferBeginInfo::builder()
-        .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
-        .inheritance_info(&inheritance_info);
+    app.device.cmd_bind_descriptor_sets(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        app.data.pipeline_layout,
+        0,
+        &[app.data.frames[app.frame].frame_set],
+        &[frame_offset],
+    );
+    app.device.cmd_draw(command_buffer, 36, 1, 0, 0);
 
-    // Record Commands
-    
-    app.device.begin_command_buffer(command_buffer, &begin_info)?;
+    end_label(&app.instance, command_buffer);
+    app.device.end_command_buffer(command_buffer)?;
 
-    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, app.data.pipeline);
+    Ok(command_buffer)
+}
+
+unsafe fn update_shadow_pass(
+    app: &super::App,
+    command_buffer: vk::CommandBuffer,
+    frame_offset: u32,
+    models: &[glm::Mat4],
+) -> Result<()>
+{
+    let render_area = vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(app.data.shadow_extent);
+
+    let depth_clear_value = vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
+    };
+
+    begin_label(&app.instance, command_buffer, "shadow pass", PASS_LABEL_COLOUR)?;
+
+    let aspects = depth_aspects(app.data.shadow_format);
+    if app.data.rendering.is_dynamic()
+    {
+        // Previous frame's main pass has to be done sampling before we write again.
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.shadow_image,
+            aspects,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
+
+        let depth_attachment = vk::RenderingAttachmentInfo::builder()
+            .image_view(app.data.shadow_image_view)
+            .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .clear_value(depth_clear_value);
+
+        let info = vk::RenderingInfo::builder()
+            .render_area(render_area)
+            .layer_count(1)
+            .depth_attachment(&depth_attachment);
+
+        begin_rendering(&app.device, &app.data, command_buffer, &info);
+    }
+    else
+    {
+        let clear_values = &[depth_clear_value];
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(app.data.shadow_render_pass)
+            .framebuffer(app.data.shadow_framebuffer)
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        app.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+    }
+
+    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, app.data.shadow_pipeline);
     app.device.cmd_bind_vertex_buffers(command_buffer, 0, &[app.data.vertex_buffer], &[0]);
     app.device.cmd_bind_index_buffer(command_buffer, app.data.index_buffer, 0, vk::IndexType::UINT32);
     app.device.cmd_bind_descriptor_sets(
         command_buffer,
         vk::PipelineBindPoint::GRAPHICS,
-        app.data.pipeline_layout,
+        app.data.shadow_pipeline_layout,
         0,
-        &[app.data.descriptor_sets[image_index]],
-        &[],
+        &[app.data.frames[app.frame].frame_set],
+        &[frame_offset],
     );
-    app.device.cmd_push_constants(
+
+    for model in models
+    {
+        let (_, model_bytes, _) = model.as_slice().align_to::<u8>();
+
+        app.device.cmd_push_constants(
+            command_buffer,
+            app.data.shadow_pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            model_bytes,
+        );
+
+        // Full detail, since shadow outlines are visible up close even for distant casters.
+        for mesh in &app.data.meshes
+        {
+            let lod = mesh.lods[0];
+            app.device.cmd_draw_indexed(command_buffer, lod.index_count, 1, lod.first_index, 0, 0);
+        }
+    }
+
+    if app.data.rendering.is_dynamic()
+    {
+        end_rendering(&app.device, &app.data, command_buffer);
+
+        // Depth writes have to land before the main pass samples the shadow map.
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.shadow_image,
+            aspects,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        );
+    }
+    else
+    {
+        app.device.cmd_end_render_pass(command_buffer);
+    }
+
+    end_label(&app.instance, command_buffer);
+
+    Ok(())
+}
+
+/// Draws model IDs into the pixel under the cursor and copies it out, to be
+/// resolved when this frame comes around again. Skipped while the cursor is
+/// outside the window.
+unsafe fn update_picking_pass<'a>(
+    app: &mut super::App,
+    command_buffer: vk::CommandBuffer,
+    frame_offset: u32,
+    instances: impl Iterator<Item = &'a DrawInstance>,
+) -> Result<()>
+{
+    let extent = app.data.swapchain_extent;
+    let cursor = match app.input.cursor
+    {
+        Some(c) if c.x >= 0.0 && c.y >= 0.0 && (c.x as u32) < extent.width && (c.y as u32) < extent.height => c,
+        _ =>
+        {
+            app.hovered = None;
+            return Ok(());
+        },
+    };
+
+    // Only the one pixel is read, so that's all that gets rasterized.
+    let pixel = vk::Rect2D
+    {
+        offset: vk::Offset2D { x: cursor.x as i32, y: cursor.y as i32 },
+        extent: vk::Extent2D { width: 1, height: 1 },
+    };
+
+    let id_clear_value = vk::ClearValue {
+        color: vk::ClearColorValue {
+            uint32: [0, 0, 0, 0],
+        },
+    };
+
+    let depth_clear_value = vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
+    };
+
+    begin_label(&app.instance, command_buffer, "picking pass", PASS_LABEL_COLOUR)?;
+
+    if app.data.rendering.is_dynamic()
+    {
+        // Also waits for the previous frame's copy to stop reading.
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.picking_image,
+            vk::ImageAspectFlags::COLOR,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        );
+
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.picking_depth_image,
+            depth_aspects(app.data.depth_format),
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
+
+        let id_attachment = vk::RenderingAttachmentInfo::builder()
+            .image_view(app.data.picking_image_view)
+            .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .clear_value(id_clear_value);
+
+        let depth_attachment = vk::RenderingAttachmentInfo::builder()
+            .image_view(app.data.picking_depth_image_view)
+            .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .clear_value(depth_clear_value);
+
+        let colour_attachments = &[id_attachment];
+        let info = vk::RenderingInfo::builder()
+            .render_area(pixel)
+            .layer_count(1)
+            .color_attachments(colour_attachments)
+            .depth_attachment(&depth_attachment);
+
+        begin_rendering(&app.device, &app.data, command_buffer, &info);
+    }
+    else
+    {
+        let clear_values = &[id_clear_value, depth_clear_value];
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(app.data.picking_render_pass)
+            .framebuffer(app.data.picking_framebuffer)
+            .render_area(pixel)
+            .clear_values(clear_values);
+
+        app.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+    }
+
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(extent.width as f32)
+        .height(extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+    app.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+    app.device.cmd_set_scissor(command_buffer, 0, &[pixel]);
+
+    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, app.data.picking_pipeline);
+    app.device.cmd_bind_vertex_buffers(command_buffer, 0, &[app.data.vertex_buffer], &[0]);
+    app.device.cmd_bind_index_buffer(command_buffer, app.data.index_buffer, 0, vk::IndexType::UINT32);
+    app.device.cmd_bind_descriptor_sets(
         command_buffer,
-        app.data.pipeline_layout,
-        vk::ShaderStageFlags::VERTEX,
+        vk::PipelineBindPoint::GRAPHICS,
+        app.data.picking_pipeline_layout,
         0,
-        model_bytes,
+        &[app.data.frames[app.frame].frame_set],
+        &[frame_offset],
     );
-    app.device.cmd_push_constants(
+
+    for instance in instances
+    {
+        let mut model = [0.0; 16];
+        model.copy_from_slice(instance.model.as_slice());
+        let constants = PickingConstants { model, id: instance.model_index as u32 + 1 };
+        let (_, constant_bytes, _) = std::slice::from_ref(&constants).align_to::<u8>();
+
+        app.device.cmd_push_constants(
+            command_buffer,
+            app.data.picking_pipeline_layout,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            constant_bytes,
+        );
+
+        for (lod, _) in &instance.lods
+        {
+            app.device.cmd_draw_indexed(command_buffer, lod.index_count, 1, lod.first_index, 0, 0);
+        }
+    }
+
+    if app.data.rendering.is_dynamic()
+    {
+        end_rendering(&app.device, &app.data, command_buffer);
+
+        // IDs have to land before the copy reads them.
+        image_barrier(
+            &app.device,
+            command_buffer,
+            app.data.picking_image,
+            vk::ImageAspectFlags::COLOR,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::AccessFlags::TRANSFER_READ,
+        );
+    }
+    else
+    {
+        app.device.cmd_end_render_pass(command_buffer);
+    }
+
+    // Readback
+
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: pixel.offset.x, y: pixel.offset.y, z: 0 })
+        .image_extent(vk::Extent3D { width: 1, height: 1, depth: 1 });
+
+    let frame = &mut app.data.frames[app.frame];
+    app.device.cmd_copy_image_to_buffer(
         command_buffer,
-        app.data.pipeline_layout,
-        vk::ShaderStageFlags::FRAGMENT,
-        64,
-        opacity_bytes,
+        app.data.picking_image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        frame.pick_buffer,
+        &[region],
     );
-    app.device.cmd_draw_indexed(command_buffer, app.data.indices.len() as u32, 1, 0, 0, 0);
 
-    app.device.end_command_buffer(command_buffer)?;
+    // Makes the copy visible to the host once the frame's fence signals.
+    let barrier = vk::MemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::HOST_READ);
 
-    Ok(command_buffer)
+    app.device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::HOST,
+        vk::DependencyFlags::empty(),
+        &[barrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[] as &[vk::ImageMemoryBarrier],
+    );
+
+    frame.pick_request = Some(PickRequest { select: app.input.pressed(Action::Select) });
+
+    end_label(&app.instance, command_buffer);
+
+    Ok(())
+}
+
+/// Transitions the scene attachments and begins rendering into them, resolving
+/// the multisampled colour into the HDR image like the render pass does.
+unsafe fn begin_scene_rendering(
+    app: &super::App,
+    command_buffer: vk::CommandBuffer,
+    render_area: vk::Rect2D,
+    color_clear_value: vk::ClearValue,
+    depth_clear_value: vk::ClearValue,
+)
+{
+    // Also waits for the previous frame's post-processing to stop sampling the resolve target.
+    for image in [app.data.colour_image, app.data.hdr_image]
+    {
+        image_barrier(
+            &app.device,
+            command_buffer,
+            image,
+            vk::ImageAspectFlags::COLOR,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        );
+    }
+
+    image_barrier(
+        &app.device,
+        command_buffer,
+        app.data.depth_image,
+        depth_aspects(app.data.depth_format),
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        vk::AccessFlags::empty(),
+        vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+        vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    );
+
+    let colour_attachment = vk::RenderingAttachmentInfo::builder()
+        .image_view(app.data.colour_image_view)
+        .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .resolve_mode(vk::ResolveModeFlags::AVERAGE)
+        .resolve_image_view(app.data.hdr_image_view)
+        .resolve_image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .clear_value(color_clear_value);
+
+    let depth_attachment = vk::RenderingAttachmentInfo::builder()
+        .image_view(app.data.depth_image_view)
+        .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .clear_value(depth_clear_value);
+
+    let colour_attachments = &[colour_attachment];
+    let info = vk::RenderingInfo::builder()
+        .flags(vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS)
+        .render_area(render_area)
+        .layer_count(1)
+        .color_attachments(colour_attachments)
+        .depth_attachment(&depth_attachment);
+
+    begin_rendering(&app.device, &app.data, command_buffer, &info);
+}
+
+/// Begins a secondary command buffer that continues the main pass.
+unsafe fn begin_secondary_command_buffer(
+    app: &super::App,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()>
+{
+    // Without a render pass the attachment formats are inherited instead.
+    let colour_formats = &[HDR_FORMAT];
+    let mut rendering_info = vk::CommandBufferInheritanceRenderingInfo::builder()
+        .color_attachment_formats(colour_formats)
+        .depth_attachment_format(app.data.depth_format)
+        .rasterization_samples(app.data.msaa_samples);
+
+    let mut inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+        .render_pass(app.data.render_pass)
+        .subpass(0)
+        .framebuffer(app.data.framebuffer);
+
+    if app.data.rendering.is_dynamic()
+    {
+        inheritance_info = inheritance_info.push_next(&mut rendering_info);
+    }
+
+    let begin_info = vk::CommandBufferBeginInfo::builder()
+        .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
+        .inheritance_info(&inheritance_info);
+
+    app.device.begin_command_buffer(command_buffer, &begin_info)?;
+
+    Ok(())
+}
+
+/// A model copy to draw this frame.
+#[derive(Clone, Debug)]
+struct DrawInstance
+{
+    model_index: usize,
+    model: glm::Mat4,
+    opacity: f32,
+    // Index ranges of the meshes inside the camera frustum, at the selected LOD,
+    // with the bindless texture of each mesh.
+    lods: Vec<(Lod, u32)>,
+}
+
+/// Culls the model copies against the camera frustum, picks their LODs and splits
+/// the rest into opaque ones and transparent ones sorted back to front.
+fn draw_instances(app: &mut super::App, models: &[glm::Mat4]) -> (Vec<DrawInstance>, Vec<DrawInstance>, FrameStats)
+{
+    let view = app.camera.view_matrix();
+    let proj = projection_matrix(app.data.swapchain_extent);
+    let frustum = Frustum::from_view_proj(&(proj * view));
+
+    app.lods.resize(models.len(), 0);
+
+    let mut stats = FrameStats::default();
+    let mut instances = vec![];
+    for (i, model) in models.iter().enumerate()
+    {
+        let size = lod::screen_size(&app.data.model_bounds, model, &view, &proj);
+        let lod_count = app.data.meshes.iter().map(|m| m.lods.len()).max().unwrap_or(1);
+        app.lods[i] = lod::select_lod(app.lods[i], lod_count, size, &app.config.lod_screen_sizes, app.config.lod_hysteresis);
+
+        let lods = app.data.meshes
+            .iter()
+            .filter(|m| frustum.intersects(&m.bounds, model))
+            .map(|m| (m.lods[app.lods[i].min(m.lods.len() - 1)], m.texture))
+            .collect::<Vec<_>>();
+
+        stats.visible += lods.len();
+        stats.culled += app.data.meshes.len() - lods.len();
+        stats.triangles += lods.iter().map(|(l, _)| l.index_count as usize / 3).sum::<usize>();
+
+        if !lods.is_empty()
+        {
+            instances.push(DrawInstance { model_index: i, model: *model, opacity: model_opacity(i), lods });
+        }
+    }
+
+    let (opaque, mut transparent): (Vec<_>, Vec<_>) = instances
+        .into_iter()
+        .partition(|i| i.opacity >= 1.0);
+
+    // The camera looks down -z in view space, so the farthest instance has the smallest z.
+    let depth = |i: &DrawInstance|
+    {
+        let (center, _) = app.data.model_bounds.sphere(&i.model);
+        (view * glm::vec4(center.x, center.y, center.z, 1.0)).z
+    };
+    transparent.sort_by(|a, b| depth(a).total_cmp(&depth(b)));
+
+    (opaque, transparent, stats)
+}
+
+/// Tint of the selected model, and a lighter one for the model under the cursor.
+fn model_highlight(app: &super::App, model_index: usize) -> f32
+{
+    if app.selected == Some(model_index)
+    {
+        1.0
+    }
+    else if app.hovered == Some(model_index)
+    {
+        0.5
+    }
+    else
+    {
+        0.0
+    }
+}
+
+fn model_opacity(model_index: usize) -> f32
+{
+    (model_index + 1) as f32 * 0.25
+}
+
+/// World transform of one of the model copies.
+fn model_matrix(app: &super::App, model_index: usize) -> glm::Mat4
+{
+    let y = (((model_index % 2) as f32) * 2.5) - 1.25;
+    let z = (((model_index / 2) as f32) * -2.0) + 1.0;
+
+    let model = glm::translate(
+        &glm::identity(), 
+        &glm::vec3(0.0, y, z),
+    );
+
+    let time = app.clock.time();
+
+    glm::rotate(
+        &model,
+        time * glm::radians(&glm::vec1(90.0))[0],
+        &glm::vec3(0.0, 0.0, 1.0))
 }
 
 pub unsafe fn begin_single_time_commands(
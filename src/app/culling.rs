@@ -0,0 +1,149 @@
+use nalgebra_glm as glm;
+
+/// Axis-aligned box and bounding sphere of a mesh in model space.
+#[derive(Copy, Clone, Debug)]
+pub struct Bounds
+{
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+    pub center: glm::Vec3,
+    pub radius: f32,
+}
+
+impl Default for Bounds
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            min: glm::Vec3::zeros(),
+            max: glm::Vec3::zeros(),
+            center: glm::Vec3::zeros(),
+            radius: 0.0,
+        }
+    }
+}
+
+impl Bounds
+{
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a glm::Vec3>) -> Self
+    {
+        let points = points.into_iter().collect::<Vec<_>>();
+        if points.is_empty()
+        {
+            return Self::default();
+        }
+
+        let mut min = *points[0];
+        let mut max = *points[0];
+        for p in &points
+        {
+            min = glm::min2(&min, p);
+            max = glm::max2(&max, p);
+        }
+
+        // Centered on the box, which is close enough for the meshes we load.
+        let center = (min + max) * 0.5;
+        let radius = points
+            .iter()
+            .map(|p| glm::distance(&center, p))
+            .fold(0.0, f32::max);
+
+        Self { min, max, center, radius }
+    }
+
+    pub fn union(&self, other: &Bounds) -> Self
+    {
+        let min = glm::min2(&self.min, &other.min);
+        let max = glm::max2(&self.max, &other.max);
+        let center = (min + max) * 0.5;
+        let radius = (glm::distance(&center, &self.center) + self.radius)
+            .max(glm::distance(&center, &other.center) + other.radius);
+
+        Self { min, max, center, radius }
+    }
+
+    /// World space center and radius of the bounding sphere.
+    pub fn sphere(&self, model: &glm::Mat4) -> (glm::Vec3, f32)
+    {
+        let center = (model * glm::vec4(self.center.x, self.center.y, self.center.z, 1.0)).xyz();
+
+        // Non-uniform scale stretches the sphere by the largest axis.
+        let scale = (0..3)
+            .map(|i| glm::length(&model.fixed_view::<3, 1>(0, i).into_owned()))
+            .fold(0.0, f32::max);
+
+        (center, self.radius * scale)
+    }
+
+    /// World space corners of the box.
+    pub fn corners(&self, model: &glm::Mat4) -> [glm::Vec3; 8]
+    {
+        let mut corners = [glm::Vec3::zeros(); 8];
+        for (i, corner) in corners.iter_mut().enumerate()
+        {
+            let x = if i & 1 == 0 { self.min.x } else { self.max.x };
+            let y = if i & 2 == 0 { self.min.y } else { self.max.y };
+            let z = if i & 4 == 0 { self.min.z } else { self.max.z };
+            *corner = (model * glm::vec4(x, y, z, 1.0)).xyz();
+        }
+
+        corners
+    }
+}
+
+/// The six planes of a view-projection, pointing inwards.
+#[derive(Copy, Clone, Debug)]
+pub struct Frustum
+{
+    planes: [glm::Vec4; 6],
+}
+
+impl Frustum
+{
+    /// Extracts the planes from the rows of the matrix (Gribb & Hartmann),
+    /// for clip space depth in `0..1` as Vulkan uses.
+    pub fn from_view_proj(m: &glm::Mat4) -> Self
+    {
+        let row = |i: usize| m.row(i).transpose();
+
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom (top with the flipped y)
+            row(3) - row(1), // top
+            row(2),          // near
+            row(3) - row(2), // far
+        ]
+        .map(|p| p / glm::length(&p.xyz()));
+
+        Self { planes }
+    }
+
+    fn distance(plane: &glm::Vec4, point: &glm::Vec3) -> f32
+    {
+        glm::dot(&plane.xyz(), point) + plane.w
+    }
+
+    pub fn intersects_sphere(&self, center: &glm::Vec3, radius: f32) -> bool
+    {
+        self.planes.iter().all(|p| Self::distance(p, center) >= -radius)
+    }
+
+    /// Conservative: only rejects boxes with every corner behind the same plane.
+    pub fn intersects_box(&self, corners: &[glm::Vec3; 8]) -> bool
+    {
+        self.planes
+            .iter()
+            .all(|p| corners.iter().any(|c| Self::distance(p, c) >= 0.0))
+    }
+
+    /// Sphere first since it's cheap, then the tighter box.
+    pub fn intersects(&self, bounds: &Bounds, model: &glm::Mat4) -> bool
+    {
+        let (center, radius) = bounds.sphere(model);
+
+        self.intersects_sphere(&center, radius)
+            && self.intersects_box(&bounds.corners(model))
+    }
+}
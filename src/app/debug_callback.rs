@@ -1,26 +1,220 @@
-
+use std::collections::HashSet;
 use std::ffi::CStr;
 use std::os::raw::c_void;
+use std::sync::Mutex;
 
 use vulkanalia::prelude::v1_0::*;
 
+use crate::config::Config;
+
+/// Decides which messenger messages are logged.
+#[derive(Clone, Debug)]
+pub struct MessageFilter
+{
+    pub min_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub muted_ids: HashSet<i32>,
+    pub muted_names: HashSet<String>,
+}
+
+impl MessageFilter
+{
+    pub fn from_config(config: &Config) -> Self
+    {
+        let mut muted_ids = HashSet::new();
+        let mut muted_names = HashSet::new();
+
+        for entry in &config.debug_mute
+        {
+            // IDs are printed by the layers as `MessageID = 0x...`, so accept hex as well.
+            let id = match entry.strip_prefix("0x")
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().map(|v| v as i32),
+                None => entry.parse::<i32>().ok(),
+            };
+
+            match id
+            {
+                Some(id) => { muted_ids.insert(id); }
+                None => { muted_names.insert(entry.clone()); }
+            }
+        }
+
+        Self { min_severity: config.debug_severity, muted_ids, muted_names }
+    }
+
+    fn allows(&self, severity: vk::DebugUtilsMessageSeverityFlagsEXT, id: i32, name: &str) -> bool
+    {
+        severity >= self.min_severity
+            && !self.muted_ids.contains(&id)
+            && !self.muted_names.contains(name)
+    }
+}
+
+impl Default for MessageFilter
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            muted_ids: HashSet::new(),
+            muted_names: HashSet::new(),
+        }
+    }
+}
+
+/// A warning or error reported through the messenger.
+#[derive(Clone, Debug)]
+pub struct ValidationMessage
+{
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub type_: vk::DebugUtilsMessageTypeFlagsEXT,
+    pub id_number: i32,
+    pub id_name: String,
+    pub message: String,
+}
+
+impl ValidationMessage
+{
+    pub fn is_error(&self) -> bool
+    {
+        self.severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+    }
+}
+
+/// Warnings and errors that passed the filter, kept so a run can be checked
+/// for validation errors afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationLog
+{
+    pub filter: MessageFilter,
+    // Whether warnings and errors are kept at all.
+    pub capture: bool,
+    messages: Vec<ValidationMessage>,
+}
+
+impl ValidationLog
+{
+    /// Returns whether the message passed the filter and should be logged.
+    pub fn record(&mut self, message: ValidationMessage) -> bool
+    {
+        if !self.filter.allows(message.severity, message.id_number, &message.id_name)
+        {
+            return false;
+        }
+
+        if self.capture && message.severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+        {
+            self.messages.push(message);
+        }
+
+        true
+    }
+
+    pub fn messages(&self) -> &[ValidationMessage]
+    {
+        &self.messages
+    }
+
+    pub fn error_count(&self) -> usize
+    {
+        self.messages.iter().filter(|m| m.is_error()).count()
+    }
+
+    pub fn first_error(&self) -> Option<&ValidationMessage>
+    {
+        self.messages.iter().find(|m| m.is_error())
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.messages.clear();
+    }
+}
+
+lazy_static! {
+    static ref LOG: Mutex<ValidationLog> = Mutex::new(ValidationLog::default());
+}
+
+/// Replaces the filter used by `debug_callback`.
+pub fn set_filter(filter: MessageFilter)
+{
+    LOG.lock().unwrap().filter = filter;
+}
+
+/// Starts or stops collecting warnings and errors for later inspection.
+pub fn set_capture(capture: bool)
+{
+    LOG.lock().unwrap().capture = capture;
+}
+
+/// The warnings and errors collected since the last `clear_messages`.
+pub fn messages() -> Vec<ValidationMessage>
+{
+    LOG.lock().unwrap().messages().to_vec()
+}
+
+pub fn error_count() -> usize
+{
+    LOG.lock().unwrap().error_count()
+}
+
+pub fn first_error() -> Option<ValidationMessage>
+{
+    LOG.lock().unwrap().first_error().cloned()
+}
+
+pub fn clear_messages()
+{
+    LOG.lock().unwrap().clear();
+}
+
 pub extern "system" fn debug_callback(
     severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     type_: vk::DebugUtilsMessageTypeFlagsEXT,
     data: *const vk::DebugUtilsMessengerCallbackDataEXT,
     _: *mut c_void,
-) -> vk::Bool32 
+) -> vk::Bool32
 {
     let data = unsafe { *data };
     let message = unsafe { CStr::from_ptr(data.message) }.to_string_lossy();
+    let id_name = if data.message_id_name.is_null()
+    {
+        "".into()
+    }
+    else
+    {
+        unsafe { CStr::from_ptr(data.message_id_name) }.to_string_lossy()
+    };
+
+    // Shader printf output is requested explicitly, so it skips the severity filter.
+    if id_name.contains("DEBUG-PRINTF")
+    {
+        log_shader_printf(&data, &message);
+        return vk::FALSE;
+    }
+
+    let validation_message = ValidationMessage
+    {
+        severity,
+        type_,
+        id_number: data.message_id_number,
+        id_name: id_name.to_string(),
+        message: message.to_string(),
+    };
 
-    if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR 
+    if !LOG.lock().map_or(true, |mut log| log.record(validation_message))
+    {
+        return vk::FALSE;
+    }
+
+    if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
     {
         error!("({:?}) {}", type_, message);
-    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING 
+    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
     {
         warn!("({:?}) {}", type_, message);
-    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::INFO 
+    } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::INFO
     {
         debug!("({:?}) {}", type_, message);
     } else
@@ -29,4 +223,139 @@ pub extern "system" fn debug_callback(
     }
 
     vk::FALSE
-}
\ No newline at end of file
+}
+
+/// Forwards a `debugPrintfEXT` message to the log with the stage and draw it came from.
+///
+/// The layer formats these as `... Stage = Fragment. ... Debug shader printf message
+/// generated in file ... at line N\n\n<text>`, with the printed text at the end.
+fn log_shader_printf(data: &vk::DebugUtilsMessengerCallbackDataEXT, message: &str)
+{
+    let text = message
+        .rsplit('\n')
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or(message)
+        .trim();
+
+    let stage = message
+        .split("Stage = ")
+        .nth(1)
+        .and_then(|s| s.split('.').next())
+        .unwrap_or("Unknown");
+
+    let draw = message
+        .split("Draw Index ")
+        .nth(1)
+        .and_then(|s| s.split('.').next())
+        .map(|i| format!(" draw {}", i))
+        .unwrap_or_default();
+
+    // Active command buffer labels, e.g. `main pass` and `model[2]`.
+    let labels = (0..data.cmd_buf_label_count as usize)
+        .filter_map(|i| 
+        {
+            let label = unsafe { *data.cmd_buf_labels.add(i) };
+            (!label.label_name.is_null())
+                .then(|| unsafe { CStr::from_ptr(label.label_name) }.to_string_lossy().into_owned())
+        })
+        .collect::<Vec<_>>()
+        .join(" > ");
+
+    info!(target: "shader", "[{}{}{}{}] {}", stage, draw, if labels.is_empty() { "" } else { " | " }, labels, text);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn message(severity: vk::DebugUtilsMessageSeverityFlagsEXT, id_number: i32, id_name: &str) -> ValidationMessage
+    {
+        ValidationMessage
+        {
+            severity,
+            type_: vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            id_number,
+            id_name: id_name.into(),
+            message: "message".into(),
+        }
+    }
+
+    fn capturing(filter: MessageFilter) -> ValidationLog
+    {
+        ValidationLog { filter, capture: true, ..Default::default() }
+    }
+
+    #[test]
+    fn mute_list_accepts_decimal_and_hex_ids_and_names()
+    {
+        let config = Config
+        {
+            debug_mute: vec!["42".into(), "0xfffffffe".into(), "VUID-Example".into()],
+            ..Default::default()
+        };
+        let filter = MessageFilter::from_config(&config);
+
+        assert!(filter.muted_ids.contains(&42));
+        assert!(filter.muted_ids.contains(&-2));
+        assert!(filter.muted_names.contains("VUID-Example"));
+    }
+
+    #[test]
+    fn messages_below_the_minimum_severity_are_dropped()
+    {
+        let mut log = capturing(MessageFilter
+        {
+            min_severity: vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            ..Default::default()
+        });
+
+        assert!(!log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING, 1, "")));
+        assert!(log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 2, "")));
+        assert_eq!(log.messages().len(), 1);
+        assert_eq!(log.error_count(), 1);
+    }
+
+    #[test]
+    fn muted_ids_and_names_are_dropped()
+    {
+        let mut log = capturing(MessageFilter
+        {
+            muted_ids: HashSet::from([7]),
+            muted_names: HashSet::from(["VUID-Muted".to_string()]),
+            ..Default::default()
+        });
+
+        assert!(!log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 7, "VUID-Other")));
+        assert!(!log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 8, "VUID-Muted")));
+        assert!(log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 9, "VUID-Other")));
+        assert_eq!(log.first_error().map(|m| m.id_number), Some(9));
+    }
+
+    #[test]
+    fn only_warnings_and_errors_are_captured()
+    {
+        let mut log = capturing(MessageFilter::default());
+
+        assert!(log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::INFO, 1, "")));
+        assert!(log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING, 2, "")));
+        assert_eq!(log.messages().len(), 1);
+        assert_eq!(log.error_count(), 0);
+        assert!(log.first_error().is_none());
+    }
+
+    #[test]
+    fn nothing_is_captured_until_enabled_and_clear_resets()
+    {
+        let mut log = ValidationLog::default();
+        log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 1, ""));
+        assert_eq!(log.error_count(), 0);
+
+        log.capture = true;
+        log.record(message(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, 1, ""));
+        assert_eq!(log.error_count(), 1);
+
+        log.clear();
+        assert_eq!(log.error_count(), 0);
+    }
+}
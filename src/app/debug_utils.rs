@@ -0,0 +1,90 @@
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Result};
+
+use vulkanalia::vk::ExtDebugUtilsExtension;
+use vulkanalia::prelude::v1_0::*;
+
+// Set once `VK_EXT_debug_utils` has been enabled on the instance.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Label colours used for the command buffer regions.
+pub const PASS_LABEL_COLOUR: [f32; 4] = [0.2, 0.6, 1.0, 1.0];
+pub const DRAW_LABEL_COLOUR: [f32; 4] = [0.4, 0.9, 0.4, 1.0];
+
+pub fn set_enabled(enabled: bool)
+{
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool
+{
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Gives a Vulkan object a name which shows up in validation messages and captures.
+pub unsafe fn set_object_name<H>(
+    instance: &Instance,
+    device: &Device,
+    handle: H,
+    name: &str,
+) -> Result<()>
+where
+    H: vk::Handle,
+    H::Repr: TryInto<u64>,
+{
+    if !is_enabled()
+    {
+        return Ok(());
+    }
+
+    let object_handle = handle
+        .as_raw()
+        .try_into()
+        .map_err(|_| anyhow!("Object handle for `{}` does not fit in 64 bits.", name))?;
+    let object_name = CString::new(name)?;
+
+    let info = vk::DebugUtilsObjectNameInfoEXT::builder()
+        .object_type(H::TYPE)
+        .object_handle(object_handle)
+        .object_name(object_name.as_bytes_with_nul());
+
+    instance.set_debug_utils_object_name_ext(device.handle(), &info)?;
+
+    Ok(())
+}
+
+/// Opens a labelled region in a command buffer, closed again by `end_label`.
+pub unsafe fn begin_label(
+    instance: &Instance,
+    command_buffer: vk::CommandBuffer,
+    name: &str,
+    colour: [f32; 4],
+) -> Result<()>
+{
+    if !is_enabled()
+    {
+        return Ok(());
+    }
+
+    let label_name = CString::new(name)?;
+    let label = vk::DebugUtilsLabelEXT::builder()
+        .label_name(label_name.as_bytes_with_nul())
+        .color(colour);
+
+    instance.cmd_begin_debug_utils_label_ext(command_buffer, &label);
+
+    Ok(())
+}
+
+pub unsafe fn end_label(
+    instance: &Instance,
+    command_buffer: vk::CommandBuffer,
+)
+{
+    if is_enabled()
+    {
+        instance.cmd_end_debug_utils_label_ext(command_buffer);
+    }
+}
@@ -2,6 +2,7 @@
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
+use super::debug_utils::set_object_name;
 
 use anyhow::{Ok, Result, anyhow};
 
@@ -12,6 +13,7 @@ pub unsafe fn create_depth_objects(
 ) -> Result<()> 
 {
     let format = get_depth_format(instance, data)?;
+    data.depth_format = format;
 
     let (depth_image, depth_image_memory) = super::texture::create_image(
         instance, 
@@ -38,6 +40,9 @@ pub unsafe fn create_depth_objects(
         1,
     )?;
 
+    set_object_name(instance, device, data.depth_image, "depth[msaa]")?;
+    set_object_name(instance, device, data.depth_image_view, "depth_view[msaa]")?;
+
     Ok(())
 }
 
@@ -61,7 +66,7 @@ pub unsafe fn get_depth_format(
     )
 }
 
-unsafe fn get_supported_format(
+pub unsafe fn get_supported_format(
     instance: &Instance,
     data: &AppData,
     candidates: &[vk::Format],
@@ -0,0 +1,171 @@
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::debug_utils::set_object_name;
+
+/// Largest pool the allocator grows to.
+const MAX_SETS_PER_POOL: u32 = 4096;
+
+/// Descriptors of one type to reserve for every set in a pool.
+#[derive(Copy, Clone, Debug)]
+pub struct PoolSizeRatio
+{
+    pub type_: vk::DescriptorType,
+    pub ratio: f32,
+}
+
+/// Hands out descriptor sets from a chain of pools, creating a bigger pool
+/// whenever the current one runs out. Sets are never freed individually and
+/// live until the allocator is destroyed.
+#[derive(Clone, Debug, Default)]
+pub struct DescriptorAllocator
+{
+    name: String,
+    ratios: Vec<PoolSizeRatio>,
+    sets_per_pool: u32,
+    ready: Vec<vk::DescriptorPool>,
+    full: Vec<vk::DescriptorPool>,
+}
+
+impl DescriptorAllocator
+{
+    pub fn new(name: &str, ratios: &[PoolSizeRatio], initial_sets: u32) -> Self
+    {
+        Self
+        {
+            name: name.to_string(),
+            ratios: ratios.to_vec(),
+            sets_per_pool: initial_sets.max(1),
+            ready: vec![],
+            full: vec![],
+        }
+    }
+
+    pub unsafe fn allocate(
+        &mut self,
+        instance: &Instance,
+        device: &Device,
+        layout: vk::DescriptorSetLayout,
+    ) -> Result<vk::DescriptorSet>
+    {
+        let set_layouts = &[layout];
+        let allocate = |pool|
+        {
+            let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(pool)
+                .set_layouts(set_layouts);
+
+            device.allocate_descriptor_sets(&allocate_info)
+        };
+
+        let pool = self.get_pool(instance, device)?;
+        let (pool, result) = match allocate(pool)
+        {
+            // Retire the pool and try once more with a fresh one.
+            Err(vk::ErrorCode::OUT_OF_POOL_MEMORY | vk::ErrorCode::FRAGMENTED_POOL) =>
+            {
+                self.full.push(pool);
+                let pool = self.get_pool(instance, device)?;
+                (pool, allocate(pool))
+            }
+            result => (pool, result),
+        };
+
+        self.ready.push(pool);
+        Ok(result?[0])
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device)
+    {
+        self.ready.drain(..).chain(self.full.drain(..)).for_each(|p| device.destroy_descriptor_pool(p, None));
+    }
+
+    unsafe fn get_pool(&mut self, instance: &Instance, device: &Device) -> Result<vk::DescriptorPool>
+    {
+        if let Some(pool) = self.ready.pop()
+        {
+            return Ok(pool);
+        }
+
+        let pool_sizes = self.pool_sizes();
+        let create_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(&pool_sizes)
+            .max_sets(self.sets_per_pool);
+
+        let pool = device.create_descriptor_pool(&create_info, None)?;
+        let index = self.ready.len() + self.full.len();
+        set_object_name(instance, device, pool, &format!("descriptor_pool:{}[{}]", self.name, index))?;
+
+        self.grow();
+
+        Ok(pool)
+    }
+
+    fn pool_sizes(&self) -> Vec<vk::DescriptorPoolSize>
+    {
+        self.ratios
+            .iter()
+            .map(|r|
+            {
+                vk::DescriptorPoolSize::builder()
+                    .type_(r.type_)
+                    .descriptor_count(((r.ratio * self.sets_per_pool as f32) as u32).max(1))
+                    .build()
+            })
+            .collect()
+    }
+
+    /// Each new pool is bigger, so a busy allocator settles on a few large pools.
+    fn grow(&mut self)
+    {
+        self.sets_per_pool = (self.sets_per_pool * 2).min(MAX_SETS_PER_POOL);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn allocator(initial_sets: u32) -> DescriptorAllocator
+    {
+        DescriptorAllocator::new(
+            "test",
+            &[
+                PoolSizeRatio { type_: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, ratio: 1.0 },
+                PoolSizeRatio { type_: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, ratio: 0.1 },
+            ],
+            initial_sets,
+        )
+    }
+
+    #[test]
+    fn pool_sizes_follow_the_ratios_with_at_least_one_descriptor()
+    {
+        let sizes = allocator(4).pool_sizes();
+
+        assert_eq!(sizes.len(), 2);
+        assert_eq!((sizes[0].type_, sizes[0].descriptor_count), (vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 4));
+        assert_eq!((sizes[1].type_, sizes[1].descriptor_count), (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 1));
+    }
+
+    #[test]
+    fn pools_double_up_to_the_limit()
+    {
+        let mut allocator = allocator(0);
+        assert_eq!(allocator.sets_per_pool, 1);
+
+        let mut sizes = vec![];
+        for _ in 0..16
+        {
+            sizes.push(allocator.sets_per_pool);
+            allocator.grow();
+        }
+
+        assert_eq!(&sizes[..4], &[1, 2, 4, 8]);
+        assert!(sizes.windows(2).all(|w| w[1] == (w[0] * 2).min(MAX_SETS_PER_POOL)));
+        assert_eq!(allocator.sets_per_pool, MAX_SETS_PER_POOL);
+        assert_eq!(allocator.pool_sizes()[1].descriptor_count, MAX_SETS_PER_POOL / 10);
+    }
+}
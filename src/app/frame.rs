@@ -0,0 +1,148 @@
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::appdata::AppData;
+use super::commands::create_command_pool;
+use super::debug_utils::set_object_name;
+use super::descriptors::{DescriptorAllocator, PoolSizeRatio};
+use super::hud::create_hud_vertex_buffer;
+use super::picking::{create_pick_buffer, PickRequest};
+use super::recording::Capture;
+use super::uniform_buffer::FrameBindings;
+use super::MAX_FRAMES_IN_FLIGHT;
+
+/// Everything one frame in flight records into or waits on.
+///
+/// None of it depends on the swapchain, so it survives resizes. Its slice of
+/// the uniform ring is the region with the same index.
+#[derive(Clone, Debug, Default)]
+pub struct FrameContext
+{
+    // Commands
+    pub command_pool: vk::CommandPool,
+    pub command_buffer: vk::CommandBuffer,
+    pub secondary_command_buffers: Vec<vk::CommandBuffer>,
+    pub skybox_command_buffer: vk::CommandBuffer,
+    // Descriptors, allocated when the frame is first recorded
+    pub descriptor_allocator: DescriptorAllocator,
+    pub frame_set: vk::DescriptorSet,
+    pub object_set: vk::DescriptorSet,
+    pub bindings: FrameBindings,
+    // HUD
+    pub hud_vertex_buffer: vk::Buffer,
+    pub hud_vertex_buffer_memory: vk::DeviceMemory,
+    // Picking, the ID under the cursor copied out when the frame was last recorded
+    pub pick_buffer: vk::Buffer,
+    pub pick_buffer_memory: vk::DeviceMemory,
+    pub pick_request: Option<PickRequest>,
+    // Recording, created on the first capture and sized to the swapchain
+    pub record_buffer: vk::Buffer,
+    pub record_buffer_memory: vk::DeviceMemory,
+    pub record_buffer_size: u64,
+    pub record_pending: Option<Capture>,
+    // Sync
+    pub image_available: vk::Semaphore,
+    pub render_finished: vk::Semaphore,
+    pub in_flight: vk::Fence,
+}
+
+pub unsafe fn create_frames(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()>
+{
+    let semaphore_info = vk::SemaphoreCreateInfo::builder();
+    let fence_info = vk::FenceCreateInfo::builder()
+        .flags(vk::FenceCreateFlags::SIGNALED);
+
+    let ratios = &[
+        PoolSizeRatio { type_: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, ratio: 1.0 },
+        PoolSizeRatio { type_: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, ratio: 1.0 },
+    ];
+
+    for i in 0..MAX_FRAMES_IN_FLIGHT
+    {
+        // Commands
+
+        let command_pool = create_command_pool(instance, device, data)?;
+        set_object_name(instance, device, command_pool, &format!("command_pool[{}]", i))?;
+
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+
+        let command_buffer = device.allocate_command_buffers(&allocate_info)?[0];
+        set_object_name(instance, device, command_buffer, &format!("primary[{}]", i))?;
+
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::SECONDARY)
+            .command_buffer_count(1);
+
+        let skybox_command_buffer = device.allocate_command_buffers(&allocate_info)?[0];
+        set_object_name(instance, device, skybox_command_buffer, &format!("skybox[{}]", i))?;
+
+        // HUD
+
+        let (hud_vertex_buffer, hud_vertex_buffer_memory) =
+            create_hud_vertex_buffer(instance, device, data, &format!("vertex:hud[{}]", i))?;
+
+        // Picking
+
+        let (pick_buffer, pick_buffer_memory) =
+            create_pick_buffer(instance, device, data, &format!("readback:pick[{}]", i))?;
+
+        // Sync
+
+        let image_available = device.create_semaphore(&semaphore_info, None)?;
+        let render_finished = device.create_semaphore(&semaphore_info, None)?;
+        let in_flight = device.create_fence(&fence_info, None)?;
+
+        set_object_name(instance, device, image_available, &format!("image_available[{}]", i))?;
+        set_object_name(instance, device, render_finished, &format!("render_finished[{}]", i))?;
+        set_object_name(instance, device, in_flight, &format!("in_flight[{}]", i))?;
+
+        data.frames.push(FrameContext
+        {
+            command_pool,
+            command_buffer,
+            secondary_command_buffers: vec![],
+            skybox_command_buffer,
+            descriptor_allocator: DescriptorAllocator::new(&format!("frame[{}]", i), ratios, 4),
+            frame_set: vk::DescriptorSet::null(),
+            object_set: vk::DescriptorSet::null(),
+            bindings: FrameBindings::default(),
+            hud_vertex_buffer,
+            hud_vertex_buffer_memory,
+            pick_buffer,
+            pick_buffer_memory,
+            pick_request: None,
+            record_buffer: vk::Buffer::null(),
+            record_buffer_memory: vk::DeviceMemory::null(),
+            record_buffer_size: 0,
+            record_pending: None,
+            image_available,
+            render_finished,
+            in_flight,
+        });
+    }
+
+    Ok(())
+}
+
+pub unsafe fn destroy_frames(device: &Device, data: &mut AppData)
+{
+    for mut frame in data.frames.drain(..)
+    {
+        device.destroy_fence(frame.in_flight, None);
+        device.destroy_semaphore(frame.render_finished, None);
+        device.destroy_semaphore(frame.image_available, None);
+        device.free_memory(frame.record_buffer_memory, None);
+        device.destroy_buffer(frame.record_buffer, None);
+        device.free_memory(frame.pick_buffer_memory, None);
+        device.destroy_buffer(frame.pick_buffer, None);
+        device.free_memory(frame.hud_vertex_buffer_memory, None);
+        device.destroy_buffer(frame.hud_vertex_buffer, None);
+        frame.descriptor_allocator.destroy(device);
+        device.destroy_command_pool(frame.command_pool, None);
+    }
+}
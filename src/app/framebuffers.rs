@@ -3,28 +3,30 @@ use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
+use super::debug_utils::set_object_name;
 
 pub unsafe fn create_framebuffers(
+    instance: &Instance,
     device: &Device, 
     data: &mut AppData
 ) -> Result<()> 
 {
-    data.framebuffers = data
-        .swapchain_image_views
-        .iter()
-        .map(|i| 
-        {
-            let attachments = &[data.colour_image_view, data.depth_image_view, *i];
-            let create_info = vk::FramebufferCreateInfo::builder()
-                .render_pass(data.render_pass)
-                .attachments(attachments)
-                .width(data.swapchain_extent.width)
-                .height(data.swapchain_extent.height)
-                .layers(1);
-        
-            device.create_framebuffer(&create_info, None)
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    if data.rendering.is_dynamic()
+    {
+        return Ok(());
+    }
+
+    // The scene is rendered offscreen, so one framebuffer serves every swapchain image.
+    let attachments = &[data.colour_image_view, data.depth_image_view, data.hdr_image_view];
+    let create_info = vk::FramebufferCreateInfo::builder()
+        .render_pass(data.render_pass)
+        .attachments(attachments)
+        .width(data.swapchain_extent.width)
+        .height(data.swapchain_extent.height)
+        .layers(1);
+
+    data.framebuffer = device.create_framebuffer(&create_info, None)?;
+    set_object_name(instance, device, data.framebuffer, "framebuffer:scene")?;
     
     Ok(())
 }
\ No newline at end of file
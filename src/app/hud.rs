@@ -0,0 +1,465 @@
+use std::mem::size_of;
+use std::ptr::copy_nonoverlapping as memcpy;
+
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::appdata::AppData;
+use super::buffer::create_buffer;
+use super::debug_utils::{begin_label, end_label, set_object_name, DRAW_LABEL_COLOUR};
+use super::font;
+use super::pipeline::create_shader_module;
+use super::texture::{create_image_view, create_texture_from_pixels};
+
+/// Glyphs (and background quads) that fit in a frame's vertex buffer.
+const MAX_QUADS: usize = 2048;
+const MARGIN: f32 = 8.0;
+const TEXT_COLOUR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BACKGROUND_COLOUR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct HudVertex
+{
+    pub position: [f32; 2],
+    pub tex_coord: [f32; 2],
+    pub colour: [f32; 4],
+}
+
+impl HudVertex
+{
+    pub fn binding_description() -> vk::VertexInputBindingDescription
+    {
+        vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(size_of::<HudVertex>() as u32)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build()
+    }
+
+    pub fn attribute_descriptions() -> [vk::VertexInputAttributeDescription; 3]
+    {
+        let position = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(0)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(0)
+            .build();
+
+        let tex_coord = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(1)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(size_of::<[f32; 2]>() as u32)
+            .build();
+
+        let colour = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(2)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(size_of::<[f32; 4]>() as u32)
+            .build();
+
+        [position, tex_coord, colour]
+    }
+}
+
+/// The font atlas, its descriptor set and the pipeline layout.
+///
+/// The set uses the post-process layout (one combined image sampler), so this has
+/// to run after `postprocess::create_post_layouts`.
+pub unsafe fn create_hud_objects(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Atlas
+
+    let (width, height) = font::atlas_size();
+    let pixels = font::build_atlas();
+    let (atlas_image, atlas_image_memory) = create_texture_from_pixels(
+        instance,
+        device,
+        data,
+        &pixels,
+        width,
+        height,
+        vk::Format::R8_UNORM,
+        "font",
+    )?;
+
+    data.hud_atlas_image = atlas_image;
+    data.hud_atlas_image_memory = atlas_image_memory;
+    data.hud_atlas_image_view = create_image_view(device, atlas_image, vk::Format::R8_UNORM, vk::ImageAspectFlags::COLOR, 1)?;
+
+    // Texels map to whole pixels, so there is nothing to filter.
+    let sampler_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::NEAREST)
+        .min_filter(vk::Filter::NEAREST)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(0.0);
+
+    data.hud_sampler = device.create_sampler(&sampler_info, None)?;
+
+    // Descriptor Set
+
+    let sampler_size = vk::DescriptorPoolSize::builder()
+        .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1);
+
+    let pool_sizes = &[sampler_size];
+    let create_info = vk::DescriptorPoolCreateInfo::builder()
+        .pool_sizes(pool_sizes)
+        .max_sets(1);
+
+    data.hud_descriptor_pool = device.create_descriptor_pool(&create_info, None)?;
+
+    let set_layouts = &[data.post_descriptor_set_layout];
+    let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(data.hud_descriptor_pool)
+        .set_layouts(set_layouts);
+
+    data.hud_descriptor_set = device.allocate_descriptor_sets(&allocate_info)?[0];
+
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(data.hud_atlas_image_view)
+        .sampler(data.hud_sampler);
+
+    let image_info = &[info];
+    let write = vk::WriteDescriptorSet::builder()
+        .dst_set(data.hud_descriptor_set)
+        .dst_binding(0)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(image_info);
+
+    device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+
+    // Layout
+
+    // Screen size in pixels.
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX)
+        .offset(0)
+        .size(8);
+
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.hud_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+
+    set_object_name(instance, device, data.hud_atlas_image_view, "texture_view:font")?;
+    set_object_name(instance, device, data.hud_sampler, "sampler:hud")?;
+    set_object_name(instance, device, data.hud_descriptor_pool, "descriptor_pool:hud")?;
+    set_object_name(instance, device, data.hud_descriptor_set, "descriptor_set:hud")?;
+    set_object_name(instance, device, data.hud_pipeline_layout, "pipeline_layout:hud")?;
+
+    Ok(())
+}
+
+/// The pipeline (for the last post-process pass) and a vertex buffer per swapchain image.
+pub unsafe fn create_hud_pipeline(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Stages
+    let vertex_shader_code = include_bytes!("../../assets/shaders/hud_vert.spv");
+    let fragment_shader_code = include_bytes!("../../assets/shaders/hud_frag.spv");
+
+    let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
+    let fragment_shader_module = create_shader_module(device, &fragment_shader_code[..])?;
+
+    let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vertex_shader_module)
+        .name(b"main\0");
+
+    let fragment_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(fragment_shader_module)
+        .name(b"main\0");
+
+    // Vertex Input State
+    let vertex_binding_descriptions = &[HudVertex::binding_description()];
+    let vertex_attribute_descriptions = &HudVertex::attribute_descriptions();
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(vertex_binding_descriptions)
+        .vertex_attribute_descriptions(vertex_attribute_descriptions);
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State, set when recording so the pipeline outlives resizes
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(false);
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    // Glyph coverage is in the fragment alpha.
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(true)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+        .alpha_blend_op(vk::BlendOp::ADD);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+
+    // Create
+    let render_pass = data.post_passes.last().map(|p| p.render_pass).unwrap_or_default();
+    let colour_formats = &[data.swapchain_format];
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .color_attachment_formats(colour_formats);
+
+    let stages = &[vertex_stage, fragment_stage];
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(data.hud_pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
+
+    data.hud_pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0];
+    set_object_name(instance, device, data.hud_pipeline, "pipeline:hud")?;
+
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    Ok(())
+}
+
+/// Host visible, since the vertices are rebuilt every frame.
+pub unsafe fn create_hud_vertex_buffer(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    name: &str,
+) -> Result<(vk::Buffer, vk::DeviceMemory)>
+{
+    let (buffer, buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        (MAX_QUADS * 6 * size_of::<HudVertex>()) as u64,
+        vk::BufferUsageFlags::VERTEX_BUFFER,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    set_object_name(instance, device, buffer, name)?;
+
+    Ok((buffer, buffer_memory))
+}
+
+/// The lines shown in the overlay.
+fn hud_lines(app: &super::App) -> Vec<String>
+{
+    let stats = &app.stats;
+    let fps = if stats.frame_time > 0.0 { 1.0 / stats.frame_time } else { 0.0 };
+
+    vec![
+        format!("FPS: {:.1} ({:.2} ms)", fps, stats.frame_time * 1000.0),
+        format!("GPU: {}", app.data.device_name),
+        format!("Present mode: {:?}", app.data.present_mode),
+        format!("View mode: {}", app.view_mode.name()),
+        format!("MSAA: {}x", app.data.msaa_samples.bits()),
+        format!("Extent: {}x{}", app.data.swapchain_extent.width, app.data.swapchain_extent.height),
+        format!("Models: {}", app.models),
+        format!("Time: {:.2} s (x{}{})", app.clock.time(), app.clock.speed, if app.clock.paused { ", paused" } else { "" }),
+        format!("Draws: {} visible, {} culled", stats.visible, stats.culled),
+        format!("Triangles: {}", stats.triangles),
+        format!("Hovered: {}", app.hovered.map_or("none".to_string(), |m| m.to_string())),
+        format!("Selected: {}", app.selected.map_or("none".to_string(), |m| m.to_string())),
+    ]
+}
+
+/// Builds two triangles per glyph, plus a background quad behind all lines.
+fn build_vertices(lines: &[String], scale: f32) -> Vec<HudVertex>
+{
+    let (atlas_width, atlas_height) = font::atlas_size();
+    let glyph = font::GLYPH_SIZE as f32 * scale;
+    let line_height = glyph + 2.0 * scale;
+
+    let mut vertices = vec![];
+    let mut quad = |x0: f32, y0: f32, x1: f32, y1: f32, uv0: [f32; 2], uv1: [f32; 2], colour: [f32; 4]|
+    {
+        let corners = [
+            ([x0, y0], [uv0[0], uv0[1]]),
+            ([x1, y0], [uv1[0], uv0[1]]),
+            ([x1, y1], [uv1[0], uv1[1]]),
+            ([x1, y1], [uv1[0], uv1[1]]),
+            ([x0, y1], [uv0[0], uv1[1]]),
+            ([x0, y0], [uv0[0], uv0[1]]),
+        ];
+
+        for (position, tex_coord) in corners
+        {
+            vertices.push(HudVertex { position, tex_coord, colour });
+        }
+    };
+
+    let uv = |c: u8|
+    {
+        let (x, y) = font::glyph_origin(c);
+        let uv0 = [x as f32 / atlas_width as f32, y as f32 / atlas_height as f32];
+        let uv1 = [
+            (x + font::GLYPH_SIZE) as f32 / atlas_width as f32,
+            (y + font::GLYPH_SIZE) as f32 / atlas_height as f32,
+        ];
+        (uv0, uv1)
+    };
+
+    // Background, sampled from the middle of the solid glyph.
+    let columns = lines.iter().map(|l| l.len()).max().unwrap_or(0) as f32;
+    let (solid0, solid1) = uv(font::SOLID_CHAR);
+    let solid = [(solid0[0] + solid1[0]) * 0.5, (solid0[1] + solid1[1]) * 0.5];
+    quad(
+        MARGIN - 4.0 * scale,
+        MARGIN - 4.0 * scale,
+        MARGIN + columns * glyph + 4.0 * scale,
+        MARGIN + lines.len() as f32 * line_height + 2.0 * scale,
+        solid,
+        solid,
+        BACKGROUND_COLOUR,
+    );
+
+    for (row, line) in lines.iter().enumerate()
+    {
+        let y = MARGIN + row as f32 * line_height;
+        for (column, c) in line.bytes().enumerate()
+        {
+            if c == b' '
+            {
+                continue;
+            }
+
+            let x = MARGIN + column as f32 * glyph;
+            let (uv0, uv1) = uv(c);
+            quad(x, y, x + glyph, y + glyph, uv0, uv1, TEXT_COLOUR);
+        }
+    }
+
+    vertices.truncate(MAX_QUADS * 6);
+    vertices
+}
+
+/// Draws the overlay into the render pass of the last post-process pass.
+pub unsafe fn record_hud(
+    app: &super::App,
+    image_index: usize,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()>
+{
+    let vertices = build_vertices(&hud_lines(app), app.config.hud_scale);
+    let frame = &app.data.frames[app.frame];
+
+    let memory = app.device.map_memory(
+        frame.hud_vertex_buffer_memory,
+        0,
+        (vertices.len() * size_of::<HudVertex>()) as u64,
+        vk::MemoryMapFlags::empty(),
+    )?;
+
+    memcpy(vertices.as_ptr(), memory.cast(), vertices.len());
+
+    app.device.unmap_memory(frame.hud_vertex_buffer_memory);
+
+    let screen_size = [app.data.swapchain_extent.width as f32, app.data.swapchain_extent.height as f32];
+    let (_, screen_size_bytes, _) = screen_size.align_to::<u8>();
+
+    begin_label(&app.instance, command_buffer, "hud", DRAW_LABEL_COLOUR)?;
+
+    app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, app.data.hud_pipeline);
+    app.device.cmd_bind_vertex_buffers(command_buffer, 0, &[frame.hud_vertex_buffer], &[0]);
+    app.device.cmd_bind_descriptor_sets(
+        command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        app.data.hud_pipeline_layout,
+        0,
+        &[app.data.hud_descriptor_set],
+        &[],
+    );
+    app.device.cmd_push_constants(
+        command_buffer,
+        app.data.hud_pipeline_layout,
+        vk::ShaderStageFlags::VERTEX,
+        0,
+        screen_size_bytes,
+    );
+    app.device.cmd_draw(command_buffer, vertices.len() as u32, 1, 0, 0);
+
+    end_label(&app.instance, command_buffer);
+
+    Ok(())
+}
+
+pub unsafe fn destroy_hud_pipeline(device: &Device, data: &AppData)
+{
+    device.destroy_pipeline(data.hud_pipeline, None);
+}
+
+pub unsafe fn destroy_hud_objects(device: &Device, data: &AppData)
+{
+    device.destroy_pipeline_layout(data.hud_pipeline_layout, None);
+    device.destroy_descriptor_pool(data.hud_descriptor_pool, None);
+    device.destroy_sampler(data.hud_sampler, None);
+    device.destroy_image_view(data.hud_atlas_image_view, None);
+    device.free_memory(data.hud_atlas_image_memory, None);
+    device.destroy_image(data.hud_atlas_image, None);
+}
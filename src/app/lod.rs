@@ -0,0 +1,90 @@
+use std::collections::{HashMap, HashSet};
+
+use nalgebra_glm as glm;
+
+use super::culling::Bounds;
+use super::vertices::Vertex;
+
+/// Vertex clustering: snaps every vertex to the first vertex that falls into the
+/// same grid cell and drops the triangles that collapse.
+pub fn simplify(
+    vertices: &[Vertex],
+    indices: &[u32],
+    origin: glm::Vec3,
+    cell_size: f32,
+) -> Vec<u32>
+{
+    let cell = |i: u32|
+    {
+        let p = (vertices[i as usize].position - origin) / cell_size;
+        (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32)
+    };
+
+    let mut representatives = HashMap::new();
+    let remapped = indices
+        .iter()
+        .map(|i| *representatives.entry(cell(*i)).or_insert(*i))
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    remapped
+        .chunks_exact(3)
+        .filter(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2])
+        .filter(|t|
+        {
+            let mut key = [t[0], t[1], t[2]];
+            key.sort_unstable();
+            seen.insert(key)
+        })
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Fraction of the screen height covered by the bounding sphere.
+pub fn screen_size(
+    bounds: &Bounds,
+    model: &glm::Mat4,
+    view: &glm::Mat4,
+    proj: &glm::Mat4,
+) -> f32
+{
+    let (center, radius) = bounds.sphere(model);
+    let distance = -(view * glm::vec4(center.x, center.y, center.z, 1.0)).z;
+
+    // Close enough to be inside the sphere, so it fills the screen.
+    if distance <= radius
+    {
+        return f32::INFINITY;
+    }
+
+    radius * proj[(1, 1)].abs() / distance
+}
+
+/// Moves from the current LOD only once the size is clearly past a threshold,
+/// so instances sitting on a boundary don't flicker between levels.
+///
+/// `thresholds[l]` is the screen size below which LOD `l + 1` is used.
+pub fn select_lod(
+    current: usize,
+    lod_count: usize,
+    size: f32,
+    thresholds: &[f32],
+    hysteresis: f32,
+) -> usize
+{
+    let lod_count = lod_count.min(thresholds.len() + 1).max(1);
+    let mut lod = current.min(lod_count - 1);
+
+    while lod + 1 < lod_count && size < thresholds[lod] * (1.0 - hysteresis)
+    {
+        lod += 1;
+    }
+
+    while lod > 0 && size > thresholds[lod - 1] * (1.0 + hysteresis)
+    {
+        lod -= 1;
+    }
+
+    lod
+}
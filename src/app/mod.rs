@@ -9,116 +9,303 @@ use vulkanalia::window as vk_window;
 use vulkanalia::vk::{ExtDebugUtilsExtension, KhrSurfaceExtension, KhrSwapchainExtension};
 use vulkanalia::prelude::v1_0::*;
 
-use winit::window::Window;
+use winit::window::{CursorGrabMode, Window};
+
+use crate::config::Config;
+use crate::display::Display;
+use crate::input::{Action, Axis, Bindings, Input};
 
 mod appdata;
+mod bindless;
+pub mod clock;
 mod buffer;
+mod camera;
 mod colour_objects;
 mod commands;
-mod debug_callback;
+mod culling;
+pub mod debug_callback;
+mod debug_utils;
 mod depth_objects;
+mod descriptors;
+mod font;
+mod frame;
 mod framebuffers;
+mod hud;
+mod lod;
 mod model;
 mod physical_device;
+mod picking;
 mod pipeline;
+pub mod postprocess;
 mod renderpass;
+pub mod recording;
+mod ring_buffer;
+mod shadow;
+mod skybox;
+pub mod stats;
 mod suitability_error;
 mod swapchain;
-mod sync_objects;
 mod texture;
 mod uniform_buffer;
 mod vertices;
+mod view_modes;
 mod queue_family_indices;
+mod rendering;
 
 const MAX_FRAMES_IN_FLIGHT: usize = 2;
-const VALIDATION_ENABLED: bool = true;
+/// Copies of the model that can be shown at once.
+pub const MAX_MODELS: usize = 4;
 const VALIDATION_LAYER: vk::ExtensionName =
     vk::ExtensionName::from_bytes(b"VK_LAYER_KHRONOS_validation");
 
 /// Our Vulkan app.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct App 
 {
     entry: Entry,
     instance: Instance,
+    config: Config,
     data: appdata::AppData,
     device: Device,
     frame: usize,
+    pub frame_count: u64,
     pub resized: bool,
-    start: Instant, 
+    pub clock: clock::Clock,
+    recorder: Option<recording::Recorder>,
     pub models: usize,
+    pub stats: stats::FrameStats,
+    pub hud_visible: bool,
+    pub view_mode: view_modes::ViewMode,
+    // Model under the cursor and the last one clicked.
+    pub hovered: Option<usize>,
+    pub selected: Option<usize>,
+    pub input: Input,
+    pub camera: camera::OrbitCamera,
+    display: Display,
+    last_frame: Instant,
+    // LOD picked for each model copy last frame.
+    lods: Vec<usize>,
 }
 
 impl App 
 {
     /// Creates our Vulkan app.
-    pub unsafe fn create(window: &Window) -> Result<Self> 
+    pub unsafe fn create(window: &Window, config: Config, bindings: Bindings) -> Result<Self> 
     {
+        let mut display = Display::new(window, config.fullscreen_mode, config.monitor, config.video_mode);
+        display.set_mode(window, config.window_mode);
+
         let loader = LibloadingLoader::new(LIBRARY)?;
         let entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
         let mut data = appdata::AppData::default();
-        let instance = create_instance(window, &entry, &mut data)?;
+        let instance = create_instance(window, &entry, &config, &mut data)?;
         data.surface = vk_window::create_surface(&instance, &window, &window)?;
 
         physical_device::pick_physical_device(&instance, &mut data)?;
 
-        let device = create_logical_device(&instance, &mut data)?;
+        let device = create_logical_device(&instance, &config, &mut data)?;
+
+        data.requested_present_mode = config.present_mode;
+        data.swapchain_capture = config.record_output.is_some();
 
         swapchain::create_swapchain(window, &instance, &device, &mut data)?;
-        swapchain::create_swapchain_image_views(&device, &mut data)?;
+        swapchain::create_swapchain_image_views(&instance, &device, &mut data)?;
 
         renderpass::create_render_pass(&instance, &device, &mut data)?;
-        uniform_buffer::create_descriptor_set_layout(&device, &mut data)?;
-        pipeline::create_pipeline(&device, &mut data)?;
+        uniform_buffer::create_descriptor_set_layouts(&instance, &device, &mut data)?;
+        bindless::create_bindless_set_layout(&instance, &device, &mut data)?;
+        pipeline::create_pipeline(&instance, &device, &mut data)?;
+        skybox::create_skybox_pipeline(&instance, &device, &mut data)?;
+
+        postprocess::create_post_layouts(&instance, &device, &mut data)?;
+
+        shadow::create_shadow_render_pass(&instance, &device, &mut data)?;
+        shadow::create_shadow_objects(&instance, &device, &config, &mut data)?;
+        shadow::create_shadow_pipeline(&instance, &device, &config, &mut data)?;
 
-        commands::create_command_pools(&instance, &device, &mut data)?;
+        picking::create_picking_render_pass(&instance, &device, &mut data)?;
+        picking::create_picking_pipeline(&instance, &device, &mut data)?;
+
+        commands::create_global_command_pool(&instance, &device, &mut data)?;
+        hud::create_hud_objects(&instance, &device, &mut data)?;
         
         colour_objects::create_colour_objects(&instance, &device, &mut data)?;
         depth_objects::create_depth_objects(&instance, &device, &mut data)?;
-        framebuffers::create_framebuffers(&device, &mut data)?;
+        framebuffers::create_framebuffers(&instance, &device, &mut data)?;
+        picking::create_picking_objects(&instance, &device, &mut data)?;
+        postprocess::create_post_passes(&instance, &device, &config, &mut data)?;
+        hud::create_hud_pipeline(&instance, &device, &mut data)?;
 
         texture::create_texture_image(&instance, &device, &mut data)?;
-        texture::create_texture_image_view(&device, &mut data)?;
-        texture::create_texture_sampler(&device, &mut data)?;
+        texture::create_texture_image_view(&instance, &device, &mut data)?;
+        texture::create_texture_sampler(&instance, &device, &mut data)?;
+        bindless::create_bindless_set(&instance, &device, &mut data)?;
+        data.descriptor_allocator = descriptors::DescriptorAllocator::new(
+            "material",
+            &[descriptors::PoolSizeRatio { type_: vk::DescriptorType::COMBINED_IMAGE_SAMPLER, ratio: 1.0 }],
+            4,
+        );
+        let (texture_image_view, texture_sampler) = (data.texture_image_view, data.texture_sampler);
+        data.texture_index = bindless::register_texture(&instance, &device, &mut data, texture_image_view, texture_sampler)?;
+        skybox::create_skybox_image(&instance, &device, &config, &mut data)?;
 
-        model::load_model(&mut data)?;
+        model::load_model(&mut data, &config)?;
         
         vertices::create_vertex_buffer(&instance, &device, &mut data)?;
         vertices::create_index_buffer(&instance, &device, &mut data)?;
-        uniform_buffer::create_uniform_buffers(&instance, &device, &mut data)?;
-        uniform_buffer::create_descriptor_pool(&device, &mut data)?;
-        uniform_buffer::create_descriptor_sets(&device, &mut data)?;
+        uniform_buffer::create_uniform_ring(&instance, &device, &mut data)?;
+        frame::create_frames(&instance, &device, &mut data)?;
 
-        commands::create_command_buffers(&device, &mut data)?;
+        if config.validation_strict
+        {
+            check_validation_errors()?;
+        }
 
-        sync_objects::create_sync_objects(&device, &mut data)?;
+        let recorder = match &config.record_output
+        {
+            Some(output) if data.swapchain_capture => Some(recording::Recorder::new(&config, output)?),
+            _ => None,
+        };
+
+        // Recordings play back at `record_fps` however long each frame took to render.
+        let time_step = match &recorder
+        {
+            Some(recorder) => Some(1.0 / recorder.fps as f64),
+            None => config.time_step,
+        };
+        let clock = match config.time_override
+        {
+            Some(time) => clock::Clock::fixed(time),
+            None => clock::Clock::new(time_step, config.time_scale, config.start_paused),
+        };
 
         Ok(Self 
         { 
             entry,
             instance,
+            hud_visible: config.hud,
+            view_mode: view_modes::ViewMode::Shaded,
+            hovered: None,
+            selected: None,
+            input: Input::new(bindings),
+            camera: camera::OrbitCamera::default(),
+            display,
+            config,
             data,
             device,
             frame: 0,
+            frame_count: 0,
             resized: false,
-            start: Instant::now(),
-            models: 4,
+            clock,
+            recorder,
+            models: MAX_MODELS,
+            stats: stats::FrameStats::default(),
+            last_frame: Instant::now(),
+            lods: vec![],
         })
     }
 
+    /// Switches to the next supported present mode, which rebuilds the swapchain.
+    pub fn cycle_present_mode(&mut self)
+    {
+        let next = swapchain::next_present_mode(self.data.present_mode, &self.data.present_modes);
+        info!("Present mode: {:?}", next);
+
+        self.data.requested_present_mode = next;
+        self.resized = true;
+    }
+
+    /// Applies the actions triggered since the last frame.
+    fn handle_input(&mut self, window: &Window)
+    {
+        // The cursor is confined while dragging so leaving the window doesn't end the orbit.
+        if self.input.pressed(Action::Orbit)
+        {
+            let _ = window.set_cursor_grab(CursorGrabMode::Confined);
+        }
+        if self.input.released(Action::Orbit)
+        {
+            let _ = window.set_cursor_grab(CursorGrabMode::None);
+        }
+        if self.input.held(Action::Orbit)
+        {
+            self.camera.orbit(self.input.axis(Axis::LookX), self.input.axis(Axis::LookY));
+        }
+        self.camera.zoom(self.input.axis(Axis::Zoom));
+
+        if self.input.pressed(Action::RemoveModel) && self.models > 1
+        {
+            self.models -= 1;
+        }
+        if self.input.pressed(Action::AddModel) && self.models < MAX_MODELS
+        {
+            self.models += 1;
+        }
+        if self.input.pressed(Action::ToggleHud)
+        {
+            self.hud_visible = !self.hud_visible;
+        }
+        if self.input.pressed(Action::CycleViewMode)
+        {
+            self.view_mode = self.view_mode.next(&self.data);
+            info!("View mode: {}", self.view_mode.name());
+        }
+        if self.input.pressed(Action::TogglePause)
+        {
+            self.clock.toggle_pause();
+        }
+        if self.input.pressed(Action::StepFrame)
+        {
+            self.clock.step();
+        }
+        if self.input.pressed(Action::SlowDown)
+        {
+            self.clock.scale_speed(0.5);
+        }
+        if self.input.pressed(Action::SpeedUp)
+        {
+            self.clock.scale_speed(2.0);
+        }
+        if self.input.pressed(Action::CyclePresentMode)
+        {
+            self.cycle_present_mode();
+        }
+        if self.input.pressed(Action::ToggleFullscreen)
+        {
+            self.display.toggle(window);
+            // Exclusive mode can keep the size and only invalidate the swapchain.
+            self.resized = true;
+        }
+    }
+
     /// Renders a frame for our Vulkan app.
     pub unsafe fn render(&mut self, window: &Window) -> Result<()> 
     {
-        let in_flight_fence = self.data.in_flight_fences[self.frame];
+        self.handle_input(window);
+
+        // Frame timing, smoothed so the HUD is readable.
+        let now = Instant::now();
+        let frame_time = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.stats.frame_time = if self.stats.frame_time == 0.0
+        {
+            frame_time
+        }
+        else
+        {
+            self.stats.frame_time * 0.95 + frame_time * 0.05
+        };
+
+        let in_flight_fence = self.data.frames[self.frame].in_flight;
 
         self.device
-            .wait_for_fences(&[in_flight_fence], true, u64::max_value())?;
+            .wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
 
         // Aquire swapchain image
         let result = self.device.acquire_next_image_khr(
             self.data.swapchain,
-            u64::max_value(),
-            self.data.image_available_semaphores[self.frame],
+            u64::MAX,
+            self.data.frames[self.frame].image_available,
             vk::Fence::null(),
         );
 
@@ -128,26 +315,19 @@ impl App
             Err(e) => return Err(anyhow!(e)),
         };
 
-        let image_in_flight = self.data.images_in_flight[image_index];
-        if !image_in_flight.is_null() 
-        {
-            self.device
-                .wait_for_fences(
-                    &[image_in_flight], 
-                    true, 
-                    u64::max_value())?;
-        }
+        // Only a frame that will be drawn advances the clock, so a skipped one
+        // neither leaves a gap in a recording nor uses up a single step.
+        self.clock.tick();
 
-        self.data.images_in_flight[image_index as usize] = in_flight_fence;
-        
+        // Everything the frame records into is owned by its context, so the
+        // fence above is the only wait needed before reusing it.
         commands::update_command_buffer(self, image_index)?;
-        uniform_buffer::update_uniform_buffer(image_index, &self.start, &self.data, &self.device)?;
 
         //Submit command buffer
-        let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
+        let wait_semaphores = &[self.data.frames[self.frame].image_available];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-        let command_buffers = &[self.data.command_buffers[image_index as usize]];
-        let signal_semaphores = &[self.data.render_finished_semaphores[self.frame]];
+        let command_buffers = &[self.data.frames[self.frame].command_buffer];
+        let signal_semaphores = &[self.data.frames[self.frame].render_finished];
         let submit_info = vk::SubmitInfo::builder()
             .wait_semaphores(wait_semaphores)
             .wait_dst_stage_mask(wait_stages)
@@ -179,6 +359,12 @@ impl App
         }
 
         self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT; 
+        self.frame_count += 1;
+
+        if self.config.validation_strict
+        {
+            check_validation_errors()?;
+        }
 
         Ok(())
     }
@@ -189,18 +375,48 @@ impl App
     {
         self.device.device_wait_idle().unwrap();
 
+        // Every frame is idle now, so their last captures can be written out.
+        if self.recorder.is_some()
+        {
+            for frame in 0..self.data.frames.len()
+            {
+                if let Err(e) = recording::resolve_capture(self, frame)
+                {
+                    error!("Recording failed: {}", e);
+                }
+            }
+            if let Some(Err(e)) = self.recorder.as_mut().map(|r| r.finish())
+            {
+                error!("Recording failed: {}", e);
+            }
+        }
+
         self.destroy_swapchain();
+        hud::destroy_hud_pipeline(&self.device, &self.data);
+        postprocess::destroy_post_passes(&self.device, &mut self.data);
+        self.device.destroy_pipeline(self.data.skybox_pipeline, None);
+        self.data.view_mode_pipelines.iter().for_each(|(_, p)| self.device.destroy_pipeline(*p, None));
+        self.device.destroy_pipeline(self.data.transparent_pipeline, None);
+        self.device.destroy_pipeline(self.data.pipeline, None);
+        self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
+        self.device.destroy_render_pass(self.data.render_pass, None);
+        self.device.destroy_swapchain_khr(self.data.swapchain, None);
 
-        self.data.in_flight_fences.iter().for_each(|f| self.device.destroy_fence(*f, None));
-        self.data.render_finished_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
-        self.data.image_available_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
-        self.data.command_pools.iter().for_each(|p| self.device.destroy_command_pool(*p, None));
+        frame::destroy_frames(&self.device, &mut self.data);
+        self.data.uniform_ring.destroy(&self.device);
 
         self.device.free_memory(self.data.index_buffer_memory, None);
         self.device.destroy_buffer(self.data.index_buffer, None);
         self.device.free_memory(self.data.vertex_buffer_memory, None);
         self.device.destroy_buffer(self.data.vertex_buffer, None);
 
+        shadow::destroy_shadow_objects(&self.device, &self.data);
+        picking::destroy_picking_pipeline(&self.device, &self.data);
+        skybox::destroy_skybox_image(&self.device, &self.data);
+        hud::destroy_hud_objects(&self.device, &self.data);
+        postprocess::destroy_post_layouts(&self.device, &self.data);
+        bindless::destroy_bindless(&self.device, &self.data);
+
         self.device.destroy_sampler(self.data.texture_sampler, None);
         self.device.destroy_image_view(self.data.texture_image_view, None);
         self.device.free_memory(self.data.texture_image_memory, None);
@@ -208,12 +424,15 @@ impl App
 
         self.device.destroy_command_pool(self.data.command_pool, None);
 
-        self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
+        self.data.descriptor_allocator.destroy(&self.device);
+        self.device.destroy_descriptor_set_layout(self.data.object_set_layout, None);
+        self.device.destroy_descriptor_set_layout(self.data.material_set_layout, None);
+        self.device.destroy_descriptor_set_layout(self.data.frame_set_layout, None);
 
         self.device.destroy_device(None);
         self.instance.destroy_surface_khr(self.data.surface, None);
 
-        if VALIDATION_ENABLED 
+        if !self.data.messenger.is_null()
         {
             self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
         }
@@ -221,47 +440,70 @@ impl App
         self.instance.destroy_instance(None);
     }
 
+    /// Rebuilds only what follows the swapchain extent; pipelines and render
+    /// passes set their viewport when recording and survive a resize.
     unsafe fn recreate_swapchain(&mut self, window: &Window) -> Result<()>
     {
         self.device.device_wait_idle()?;
         self.destroy_swapchain();
+
+        let old_format = self.data.swapchain_format;
         swapchain::create_swapchain(window, &self.instance, &self.device, &mut self.data)?;
-        swapchain::create_swapchain_image_views(&self.device, &mut self.data)?;
-        renderpass::create_render_pass(&self.instance, &self.device, &mut self.data)?;
-        pipeline::create_pipeline(&self.device, &mut self.data)?;
+        swapchain::create_swapchain_image_views(&self.instance, &self.device, &mut self.data)?;
+
         colour_objects::create_colour_objects(&self.instance, &self.device, &mut self.data)?;
         depth_objects::create_depth_objects(&self.instance, &self.device, &mut self.data)?;
-        framebuffers::create_framebuffers(&self.device, &mut self.data)?;
-        uniform_buffer::create_uniform_buffers(&self.instance, &self.device, &mut self.data)?;
-        uniform_buffer::create_descriptor_pool(&self.device, &mut self.data)?;
-        uniform_buffer::create_descriptor_sets(&self.device, &mut self.data)?;
-        commands::create_command_buffers(&self.device, &mut self.data)?;
-        self.data
-            .images_in_flight
-            .resize(self.data.swapchain_images.len(), vk::Fence::null());
+        framebuffers::create_framebuffers(&self.instance, &self.device, &mut self.data)?;
+        picking::create_picking_objects(&self.instance, &self.device, &mut self.data)?;
+
+        // The last post pass and the HUD write to the swapchain, so they only
+        // need rebuilding when the surface format changes.
+        if self.data.swapchain_format != old_format
+        {
+            hud::destroy_hud_pipeline(&self.device, &self.data);
+            postprocess::destroy_post_passes(&self.device, &mut self.data);
+            postprocess::create_post_passes(&self.instance, &self.device, &self.config, &mut self.data)?;
+            hud::create_hud_pipeline(&self.instance, &self.device, &mut self.data)?;
+        }
+        else
+        {
+            postprocess::create_post_targets(&self.instance, &self.device, &mut self.data)?;
+        }
+
         Ok(())
     }
 
+    /// Destroys the objects sized to the swapchain, keeping the swapchain
+    /// itself so it can be handed to its replacement.
     unsafe fn destroy_swapchain(&mut self)
     {
-        self.device.destroy_descriptor_pool(self.data.descriptor_pool, None);
-        self.data.uniform_buffers_memory.iter().for_each(|m| self.device.free_memory(*m, None));
-        self.data.uniform_buffers.iter().for_each(|b| self.device.destroy_buffer(*b, None));
+        postprocess::destroy_post_targets(&self.device, &mut self.data);
+        picking::destroy_picking_objects(&self.device, &self.data);
 
         self.device.destroy_image_view(self.data.colour_image_view, None);
         self.device.free_memory(self.data.colour_image_memory, None);
         self.device.destroy_image(self.data.colour_image, None);
 
+        self.device.destroy_image_view(self.data.hdr_image_view, None);
+        self.device.free_memory(self.data.hdr_image_memory, None);
+        self.device.destroy_image(self.data.hdr_image, None);
+
         self.device.destroy_image_view(self.data.depth_image_view, None);
         self.device.free_memory(self.data.depth_image_memory, None);
         self.device.destroy_image(self.data.depth_image, None);
 
-        self.data.framebuffers.iter().for_each(|f| self.device.destroy_framebuffer(*f, None));
-        self.device.destroy_pipeline(self.data.pipeline, None);
-        self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
-        self.device.destroy_render_pass(self.data.render_pass, None);
+        self.device.destroy_framebuffer(self.data.framebuffer, None);
         self.data.swapchain_image_views.iter().for_each(|v| self.device.destroy_image_view(*v, None));
-        self.device.destroy_swapchain_khr(self.data.swapchain, None);
+    }
+}
+
+/// Fails on the first validation error collected by the debug callback.
+fn check_validation_errors() -> Result<()>
+{
+    match debug_callback::first_error()
+    {
+        Some(error) => Err(anyhow!("Validation error `{}`: {}", error.id_name, error.message)),
+        None => Ok(()),
     }
 }
 
@@ -269,16 +511,19 @@ impl App
 unsafe fn create_instance(
     window: &Window, 
     entry: &Entry,
+    config: &Config,
     data: &mut appdata::AppData
 ) -> Result<Instance> 
 {
+    data.api_version = rendering::instance_api_version(entry);
+
     // Optional Application information
     let application_info = vk::ApplicationInfo::builder()
         .application_name(b"Vulkan Tutorial\0")
         .application_version(vk::make_version(1, 0, 0))
         .engine_name(b"No Engine\0")
         .engine_version(vk::make_version(1, 0, 0))
-        .api_version(vk::make_version(1, 0, 0));
+        .api_version(data.api_version);
 
     // Validation layers extensions
     let available_layers = entry
@@ -287,12 +532,13 @@ unsafe fn create_instance(
         .map(|l| l.layer_name)
         .collect::<HashSet<_>>();
 
-    if VALIDATION_ENABLED && !available_layers.contains(&VALIDATION_LAYER) 
+    data.validation_enabled = config.validation && available_layers.contains(&VALIDATION_LAYER);
+    if config.validation && !data.validation_enabled
     {
-        return Err(anyhow!("Validationlayer requested but not supported."));
+        warn!("Validation layer requested but not supported, continuing without validation.");
     }
 
-    let layers = if VALIDATION_ENABLED 
+    let layers = if data.validation_enabled 
     {
         vec![VALIDATION_LAYER.as_ptr()]
     }
@@ -307,10 +553,78 @@ unsafe fn create_instance(
         .map(|e| e.as_ptr())
         .collect::<Vec<_>>();
 
-    if VALIDATION_ENABLED 
+    let mut available_extensions = entry
+        .enumerate_instance_extension_properties(None)?
+        .iter()
+        .map(|e| e.extension_name)
+        .collect::<HashSet<_>>();
+
+    if data.validation_enabled
+    {
+        let layer_name = VALIDATION_LAYER.as_cstr().to_bytes_with_nul();
+        available_extensions.extend(entry
+            .enumerate_instance_extension_properties(Some(layer_name))?
+            .iter()
+            .map(|e| e.extension_name));
+    }
+
+    // Object names and labels are useful in captures even without the validation layer.
+    let debug_utils_enabled = available_extensions.contains(&vk::EXT_DEBUG_UTILS_EXTENSION.name);
+    if debug_utils_enabled 
     {
         extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION.name.as_ptr());
     }
+    debug_utils::set_enabled(debug_utils_enabled);
+
+    // A check that can't see validation messages would pass without checking anything.
+    let checking = config.validation_capture || config.validation_strict;
+    if checking && !(data.validation_enabled && debug_utils_enabled)
+    {
+        return Err(anyhow!("Validation capture or strict mode needs the validation layer and `VK_EXT_debug_utils`."));
+    }
+
+    // Optional validation features
+    let mut enabled_features = vec![];
+    if config.validation_synchronization
+    {
+        enabled_features.push(vk::ValidationFeatureEnableEXT::SYNCHRONIZATION_VALIDATION);
+    }
+    if config.validation_best_practices
+    {
+        enabled_features.push(vk::ValidationFeatureEnableEXT::BEST_PRACTICES);
+    }
+    if config.debug_printf
+    {
+        // Debug printf and GPU-assisted validation instrument shaders the same way and cannot be combined.
+        if config.validation_gpu_assisted
+        {
+            warn!("Debug printf requested, disabling GPU-assisted validation.");
+        }
+        enabled_features.push(vk::ValidationFeatureEnableEXT::DEBUG_PRINTF);
+    }
+    else if config.validation_gpu_assisted
+    {
+        enabled_features.push(vk::ValidationFeatureEnableEXT::GPU_ASSISTED);
+        enabled_features.push(vk::ValidationFeatureEnableEXT::GPU_ASSISTED_RESERVE_BINDING_SLOT);
+    }
+
+    let validation_features_enabled = data.validation_enabled
+        && !enabled_features.is_empty()
+        && available_extensions.contains(&vk::EXT_VALIDATION_FEATURES_EXTENSION.name);
+    if data.validation_enabled && !enabled_features.is_empty() && !validation_features_enabled
+    {
+        warn!("Validation features requested but `VK_EXT_validation_features` is not supported.");
+    }
+    if validation_features_enabled
+    {
+        extensions.push(vk::EXT_VALIDATION_FEATURES_EXTENSION.name.as_ptr());
+    }
+
+    data.debug_printf_enabled = validation_features_enabled && config.debug_printf;
+    if config.debug_printf && !data.debug_printf_enabled
+    {
+        warn!("Debug printf requested but validation features are not available.");
+    }
 
     // Define Create info using the application information and global extensions
     let mut info = vk::InstanceCreateInfo::builder()
@@ -319,6 +633,9 @@ unsafe fn create_instance(
         .enabled_extension_names(&extensions);
     
     // Create debug info
+    debug_callback::set_filter(debug_callback::MessageFilter::from_config(config));
+    debug_callback::set_capture(config.validation_capture || config.validation_strict);
+
     let mut debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
         .message_severity(vk::DebugUtilsMessageSeverityFlagsEXT::all())
         .message_type(
@@ -328,15 +645,23 @@ unsafe fn create_instance(
         )
         .user_callback(Some(debug_callback::debug_callback));
 
-    if VALIDATION_ENABLED 
+    if debug_utils_enabled 
     {
         info = info.push_next(&mut debug_info);
     }
+
+    let mut validation_features = vk::ValidationFeaturesEXT::builder()
+        .enabled_validation_features(&enabled_features);
+
+    if validation_features_enabled
+    {
+        info = info.push_next(&mut validation_features);
+    }
     
     // Create instance
     let instance = entry.create_instance(&info, None)?;
 
-    if VALIDATION_ENABLED 
+    if debug_utils_enabled 
     {
         data.messenger = instance.create_debug_utils_messenger_ext(&debug_info, None)?;
     }
@@ -347,6 +672,7 @@ unsafe fn create_instance(
 // create logical device
 unsafe fn create_logical_device(
     instance: &Instance,
+    config: &Config,
     data: &mut appdata::AppData,
 ) -> Result<Device> 
 {
@@ -367,7 +693,7 @@ unsafe fn create_logical_device(
         })
         .collect::<Vec<_>>();
 
-    let layers = if VALIDATION_ENABLED 
+    let layers = if data.validation_enabled 
     {
         vec![VALIDATION_LAYER.as_ptr()]
     } else 
@@ -375,22 +701,88 @@ unsafe fn create_logical_device(
         vec![]
     };
 
-    let extensions = physical_device::DEVICE_EXTENSIONS
+    let mut extensions = physical_device::DEVICE_EXTENSIONS
         .iter()
         .map(|n| n.as_ptr())
         .collect::<Vec<_>>();
 
+    // `debugPrintfEXT` compiles to a non-semantic instruction.
+    if data.debug_printf_enabled
+    {
+        if physical_device::supports_extension(instance, data.physical_device, &vk::KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION.name)?
+        {
+            extensions.push(vk::KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION.name.as_ptr());
+        }
+        else
+        {
+            warn!("Debug printf requested but `VK_KHR_shader_non_semantic_info` is not supported.");
+            data.debug_printf_enabled = false;
+        }
+    }
+
+    data.rendering = rendering::select_rendering_path(instance, config, data)?;
+    if data.rendering == rendering::RenderingPath::Extension
+    {
+        extensions.push(vk::KHR_DYNAMIC_RENDERING_EXTENSION.name.as_ptr());
+    }
+
+    data.descriptor_indexing = bindless::select_descriptor_indexing(instance, config, data)?;
+    if data.descriptor_indexing == bindless::DescriptorIndexing::Extension
+    {
+        extensions.push(vk::EXT_DESCRIPTOR_INDEXING_EXTENSION.name.as_ptr());
+    }
+
+    // GPU-assisted validation and debug printf instrument shaders with storage writes.
+    let supported_features = instance.get_physical_device_features(data.physical_device);
+    let instrumented = data.validation_enabled && (config.validation_gpu_assisted || data.debug_printf_enabled);
+    let stores_and_atomics = instrumented
+        && supported_features.fragment_stores_and_atomics == vk::TRUE
+        && supported_features.vertex_pipeline_stores_and_atomics == vk::TRUE;
+    if instrumented && !stores_and_atomics
+    {
+        warn!("Shader instrumentation requested but shader stores and atomics are not supported.");
+    }
+
+    // Line polygon mode for the wireframe view modes.
+    data.fill_mode_non_solid = supported_features.fill_mode_non_solid == vk::TRUE;
+    if !data.fill_mode_non_solid
+    {
+        warn!("`fillModeNonSolid` is not supported, the wireframe view modes are disabled.");
+    }
+
     let features = vk::PhysicalDeviceFeatures::builder()
         .sampler_anisotropy(true)
         // Enable sample shading feature for the device.
-        .sample_rate_shading(true);
+        .sample_rate_shading(true)
+        .fill_mode_non_solid(data.fill_mode_non_solid)
+        .fragment_stores_and_atomics(stores_and_atomics)
+        .vertex_pipeline_stores_and_atomics(stores_and_atomics);
+
+    let mut dynamic_rendering_features = vk::PhysicalDeviceDynamicRenderingFeatures::builder()
+        .dynamic_rendering(true);
+
+    let mut descriptor_indexing_features = vk::PhysicalDeviceDescriptorIndexingFeatures::builder()
+        .runtime_descriptor_array(true)
+        .descriptor_binding_partially_bound(true)
+        .descriptor_binding_sampled_image_update_after_bind(true)
+        .shader_sampled_image_array_non_uniform_indexing(true);
 
-    let info = vk::DeviceCreateInfo::builder()
+    let mut info = vk::DeviceCreateInfo::builder()
         .queue_create_infos(&queue_infos)
         .enabled_layer_names(&layers)
         .enabled_extension_names(&extensions)
         .enabled_features(&features);
 
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut dynamic_rendering_features);
+    }
+
+    if data.descriptor_indexing.is_enabled()
+    {
+        info = info.push_next(&mut descriptor_indexing_features);
+    }
+
     let device = instance.create_device(data.physical_device, &info, None)?;
 
     data.graphics_queue = device.get_device_queue(indices.graphics, 0);
@@ -1,22 +1,107 @@
 
 use std::fs::File;
+use std::path::Path;
 
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::io::BufReader;
+use std::ops::Range;
 
 use nalgebra_glm as glm;
 
+use crate::config::Config;
+
 use super::appdata::AppData;
+use super::culling::Bounds;
+use super::lod;
 use super::vertices::Vertex;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+
+/// A range of the shared index buffer.
+#[derive(Copy, Clone, Debug)]
+pub struct Lod
+{
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+/// A mesh with its bounds and level-of-detail index ranges, finest first.
+#[derive(Clone, Debug)]
+pub struct Mesh
+{
+    pub name: String,
+    pub lods: Vec<Lod>,
+    pub bounds: Bounds,
+    /// Slot in the bindless texture array.
+    pub texture: u32,
+}
+
+const MODEL_PATH: &str = "assets/models/viking_room.obj";
 
 pub fn load_model(
-    data: &mut AppData
+    data: &mut AppData,
+    config: &Config,
 ) -> Result<()>
 {
-    let mut reader = BufReader::new(File::open("assets/models/viking_room.obj")?);
+    let mut unique_vertices = HashMap::new();
+
+    let meshes = load_obj(MODEL_PATH, data, &mut unique_vertices)?;
+    for (name, indices) in meshes
+    {
+        let bounds = Bounds::from_points(
+            data.indices[indices.clone()].iter().map(|i| &data.vertices[*i as usize].position));
+
+        data.meshes.push(Mesh
+        {
+            name,
+            lods: vec![Lod { first_index: indices.start as u32, index_count: indices.len() as u32 }],
+            bounds,
+            texture: data.texture_index,
+        });
+    }
+
+    // Authored LODs are `<model>.lod1.obj`, `<model>.lod2.obj`, ... with the same meshes.
+    let authored = (1..=config.lod_screen_sizes.len())
+        .map(|l| MODEL_PATH.replace(".obj", &format!(".lod{}.obj", l)))
+        .take_while(|p| Path::new(p).exists())
+        .collect::<Vec<_>>();
+
+    if authored.is_empty()
+    {
+        generate_lods(data, config);
+    }
+    else
+    {
+        for path in authored
+        {
+            load_authored_lod(&path, data, &mut unique_vertices)?;
+        }
+    }
+
+    for mesh in &data.meshes
+    {
+        let triangles = mesh.lods.iter().map(|l| (l.index_count / 3).to_string()).collect::<Vec<_>>();
+        info!("Mesh `{}` LOD triangles: {}.", mesh.name, triangles.join(", "));
+    }
+
+    data.model_bounds = data.meshes
+        .iter()
+        .map(|m| m.bounds)
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_default();
+    
+    Ok(())
+}
+
+/// Appends the meshes of an OBJ file, returning their names and index ranges.
+fn load_obj(
+    path: &str,
+    data: &mut AppData,
+    unique_vertices: &mut HashMap<Vertex, usize>,
+) -> Result<Vec<(String, Range<usize>)>>
+{
+    let mut reader = BufReader::new(File::open(path)?);
 
     let (models, _) = tobj::load_obj_buf(
         &mut reader, 
@@ -24,10 +109,12 @@ pub fn load_model(
         |_| Ok(Default::default()),
     )?;
 
-    let mut unique_vertices = HashMap::new();
+    let mut meshes = vec![];
 
     for model in &models
     {
+        let first_index = data.indices.len();
+
         for index in &model.mesh.indices
         {
             let position_offset = (3 * index) as usize;
@@ -59,11 +146,62 @@ pub fn load_model(
                 data.indices.push(index as u32);
             }
         }
+
+        meshes.push((model.name.clone(), first_index..data.indices.len()));
     }
-    
+
+    Ok(meshes)
+}
+
+fn load_authored_lod(
+    path: &str,
+    data: &mut AppData,
+    unique_vertices: &mut HashMap<Vertex, usize>,
+) -> Result<()>
+{
+    let meshes = load_obj(path, data, unique_vertices)?;
+    if meshes.len() != data.meshes.len()
+    {
+        return Err(anyhow!("`{}` has {} meshes, expected {}.", path, meshes.len(), data.meshes.len()));
+    }
+
+    for (mesh, (_, indices)) in data.meshes.iter_mut().zip(meshes)
+    {
+        mesh.lods.push(Lod { first_index: indices.start as u32, index_count: indices.len() as u32 });
+    }
+
     Ok(())
 }
 
+/// Simplifies every mesh once per configured LOD, doubling the cluster size each time.
+fn generate_lods(data: &mut AppData, config: &Config)
+{
+    for mesh_index in 0..data.meshes.len()
+    {
+        let bounds = data.meshes[mesh_index].bounds;
+        let full = data.meshes[mesh_index].lods[0];
+        let mut cell_size = glm::comp_max(&(bounds.max - bounds.min)) / config.lod_grid_resolution as f32;
+
+        for _ in 0..config.lod_screen_sizes.len()
+        {
+            let source = &data.indices[full.first_index as usize..(full.first_index + full.index_count) as usize];
+            let simplified = lod::simplify(&data.vertices, source, bounds.min, cell_size);
+            cell_size *= 2.0;
+
+            // Nothing left to remove, or nothing left to draw.
+            let previous = data.meshes[mesh_index].lods.last().unwrap().index_count as usize;
+            if simplified.is_empty() || simplified.len() >= previous
+            {
+                break;
+            }
+
+            let lod = Lod { first_index: data.indices.len() as u32, index_count: simplified.len() as u32 };
+            data.indices.extend(simplified);
+            data.meshes[mesh_index].lods.push(lod);
+        }
+    }
+}
+
 impl PartialEq for Vertex {
     fn eq(&self, other: &Self) -> bool 
     {
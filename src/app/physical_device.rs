@@ -24,6 +24,7 @@ pub unsafe fn pick_physical_device(instance: &Instance, data: &mut AppData) -> R
         } else 
         {
             info!("Selected physical device (`{}`).", properties.device_name);
+            data.device_name = properties.device_name.to_string();
 
             data.physical_device = physical_device;
             data.msaa_samples = get_max_msaa_samples(instance, data);
@@ -122,4 +123,16 @@ pub unsafe fn check_physical_device_extensions(
     {
         Err(anyhow!(SuitabilityError("Missing required device extensions.")))
     }
-}
\ No newline at end of file
+}
+
+pub unsafe fn supports_extension(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    extension: &vk::ExtensionName,
+) -> Result<bool>
+{
+    Ok(instance
+        .enumerate_device_extension_properties(physical_device, None)?
+        .iter()
+        .any(|e| e.extension_name == *extension))
+}
@@ -0,0 +1,383 @@
+use std::mem::size_of;
+
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::appdata::AppData;
+use super::buffer::create_buffer;
+use super::debug_utils::set_object_name;
+use super::depth_objects::get_depth_format;
+use super::pipeline::create_shader_module;
+use super::texture::{create_image, create_image_view};
+use super::vertices::Vertex;
+
+/// One ID per pixel: the model index + 1, or 0 where nothing was drawn.
+pub const PICKING_FORMAT: vk::Format = vk::Format::R32_UINT;
+
+/// Push constants of the picking pipeline, shared by both stages.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PickingConstants
+{
+    pub model: [f32; 16],
+    pub id: u32,
+}
+
+/// A readback recorded into a frame, resolved once its fence has signaled.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PickRequest
+{
+    // Whether the result becomes the selection, not just the hovered model.
+    pub select: bool,
+}
+
+pub unsafe fn create_picking_render_pass(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    if data.rendering.is_dynamic()
+    {
+        return Ok(());
+    }
+
+    // Left ready for the copy of the pixel under the cursor.
+    let id_attachment = vk::AttachmentDescription::builder()
+        .format(PICKING_FORMAT)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(get_depth_format(instance, data)?)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let id_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+    let depth_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let color_attachments = &[id_attachment_reference];
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(color_attachments)
+        .depth_stencil_attachment(&depth_attachment_reference);
+
+    // The previous frame's copy has to be done reading before we clear again.
+    let before = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(vk::PipelineStageFlags::TRANSFER
+            | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+            | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+        .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+    // IDs have to land before the copy reads them.
+    let after = vk::SubpassDependency::builder()
+        .src_subpass(0)
+        .dst_subpass(vk::SUBPASS_EXTERNAL)
+        .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::TRANSFER)
+        .dst_access_mask(vk::AccessFlags::TRANSFER_READ);
+
+    let attachments = &[id_attachment, depth_attachment];
+    let subpasses = &[subpass];
+    let dependencies = &[before, after];
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+
+    data.picking_render_pass = device.create_render_pass(&create_info, None)?;
+    set_object_name(instance, device, data.picking_render_pass, "render_pass:picking")?;
+
+    Ok(())
+}
+
+/// The ID and depth targets, which follow the swapchain extent.
+pub unsafe fn create_picking_objects(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // ID
+
+    let (picking_image, picking_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.swapchain_extent.width,
+        data.swapchain_extent.height,
+        1,
+        vk::SampleCountFlags::_1,
+        PICKING_FORMAT,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.picking_image = picking_image;
+    data.picking_image_memory = picking_image_memory;
+    data.picking_image_view = create_image_view(device, picking_image, PICKING_FORMAT, vk::ImageAspectFlags::COLOR, 1)?;
+
+    set_object_name(instance, device, data.picking_image, "picking")?;
+    set_object_name(instance, device, data.picking_image_view, "picking_view")?;
+
+    // Depth
+
+    let depth_format = get_depth_format(instance, data)?;
+    let (picking_depth_image, picking_depth_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.swapchain_extent.width,
+        data.swapchain_extent.height,
+        1,
+        vk::SampleCountFlags::_1,
+        depth_format,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.picking_depth_image = picking_depth_image;
+    data.picking_depth_image_memory = picking_depth_image_memory;
+    data.picking_depth_image_view = create_image_view(device, picking_depth_image, depth_format, vk::ImageAspectFlags::DEPTH, 1)?;
+
+    set_object_name(instance, device, data.picking_depth_image, "depth[picking]")?;
+    set_object_name(instance, device, data.picking_depth_image_view, "depth_view[picking]")?;
+
+    // Framebuffer
+
+    if !data.rendering.is_dynamic()
+    {
+        let attachments = &[data.picking_image_view, data.picking_depth_image_view];
+        let framebuffer_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(data.picking_render_pass)
+            .attachments(attachments)
+            .width(data.swapchain_extent.width)
+            .height(data.swapchain_extent.height)
+            .layers(1);
+
+        data.picking_framebuffer = device.create_framebuffer(&framebuffer_info, None)?;
+        set_object_name(instance, device, data.picking_framebuffer, "framebuffer:picking")?;
+    }
+
+    Ok(())
+}
+
+pub unsafe fn create_picking_pipeline(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Stages
+    let vertex_shader_code = include_bytes!("../../assets/shaders/picking_vert.spv");
+    let fragment_shader_code = include_bytes!("../../assets/shaders/picking_frag.spv");
+
+    let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
+    let fragment_shader_module = create_shader_module(device, &fragment_shader_code[..])?;
+
+    let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vertex_shader_module)
+        .name(b"main\0");
+
+    let fragment_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(fragment_shader_module)
+        .name(b"main\0");
+
+    // Vertex Input State
+    let vertex_binding_descriptions = &[Vertex::binding_description()];
+    let vertex_attribute_descriptions = &Vertex::attribute_descriptions();
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(vertex_binding_descriptions)
+        .vertex_attribute_descriptions(vertex_attribute_descriptions);
+
+    // Input Assembly State
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State, set when recording so the pipeline outlives resizes
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::BACK)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(false);
+
+    // IDs can't be blended, so there is no multisampling either.
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    // Depth Stencil State
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    // Color Blend State
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::R)
+        .blend_enable(false);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+        .offset(0)
+        .size(size_of::<PickingConstants>() as u32);
+
+    // Layout
+    let set_layouts = &[data.frame_set_layout];
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.picking_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+    set_object_name(instance, device, data.picking_pipeline_layout, "pipeline_layout:picking")?;
+
+    // Create
+    let colour_formats = &[PICKING_FORMAT];
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .color_attachment_formats(colour_formats)
+        .depth_attachment_format(get_depth_format(instance, data)?);
+
+    let stages = &[vertex_stage, fragment_stage];
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(data.picking_pipeline_layout)
+        .render_pass(data.picking_render_pass)
+        .subpass(0);
+
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
+
+    data.picking_pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0];
+    set_object_name(instance, device, data.picking_pipeline, "pipeline:picking")?;
+
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    Ok(())
+}
+
+/// A host visible buffer for one frame's picked ID.
+pub unsafe fn create_pick_buffer(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    name: &str,
+) -> Result<(vk::Buffer, vk::DeviceMemory)>
+{
+    let (buffer, buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size_of::<u32>() as u64,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    set_object_name(instance, device, buffer, name)?;
+
+    Ok((buffer, buffer_memory))
+}
+
+/// Resolves the readback this frame recorded last time, if any; its fence has
+/// signaled by the time the frame is recorded again.
+pub unsafe fn resolve_pick(app: &mut super::App) -> Result<()>
+{
+    let frame = &mut app.data.frames[app.frame];
+    let Some(request) = frame.pick_request.take() else { return Ok(()) };
+
+    let memory = app.device.map_memory(frame.pick_buffer_memory, 0, size_of::<u32>() as u64, vk::MemoryMapFlags::empty())?;
+    let id = *(memory as *const u32);
+    app.device.unmap_memory(frame.pick_buffer_memory);
+
+    // Models can be removed while their readback is in flight.
+    app.hovered = (id as usize).checked_sub(1).filter(|m| *m < app.models);
+    if request.select
+    {
+        app.selected = app.hovered;
+        info!("Selected model: {:?}", app.selected);
+    }
+
+    Ok(())
+}
+
+pub unsafe fn destroy_picking_objects(device: &Device, data: &AppData)
+{
+    device.destroy_framebuffer(data.picking_framebuffer, None);
+    device.destroy_image_view(data.picking_depth_image_view, None);
+    device.free_memory(data.picking_depth_image_memory, None);
+    device.destroy_image(data.picking_depth_image, None);
+    device.destroy_image_view(data.picking_image_view, None);
+    device.free_memory(data.picking_image_memory, None);
+    device.destroy_image(data.picking_image, None);
+}
+
+pub unsafe fn destroy_picking_pipeline(device: &Device, data: &AppData)
+{
+    device.destroy_pipeline(data.picking_pipeline, None);
+    device.destroy_pipeline_layout(data.picking_pipeline_layout, None);
+    device.destroy_render_pass(data.picking_render_pass, None);
+}
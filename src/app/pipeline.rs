@@ -3,25 +3,190 @@ use vulkanalia::prelude::v1_0::*;
 
 use super::vertices::Vertex;
 use super::appdata::AppData;
+use super::depth_objects::get_depth_format;
+use super::postprocess::HDR_FORMAT;
+use super::debug_utils::set_object_name;
+use super::uniform_buffer::{FAR_PLANE, NEAR_PLANE};
+use super::view_modes::ViewMode;
 
-pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()> 
+/// How a scene pipeline blends into the HDR target.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Blend
+{
+    Opaque,
+    Alpha,
+    Additive,
+}
+
+/// The fixed-function state that differs between the scene pipelines, which
+/// otherwise share their shaders, layout and attachments.
+#[derive(Copy, Clone, Debug)]
+struct SceneVariant
+{
+    view_mode: ViewMode,
+    polygon_mode: vk::PolygonMode,
+    cull_mode: vk::CullModeFlags,
+    depth_test: bool,
+    depth_write: bool,
+    depth_compare_op: vk::CompareOp,
+    blend: Blend,
+}
+
+impl SceneVariant
+{
+    const OPAQUE: SceneVariant = SceneVariant
+    {
+        view_mode: ViewMode::Shaded,
+        polygon_mode: vk::PolygonMode::FILL,
+        cull_mode: vk::CullModeFlags::BACK,
+        depth_test: true,
+        depth_write: true,
+        depth_compare_op: vk::CompareOp::LESS,
+        blend: Blend::Opaque,
+    };
+
+    // Transparent draws are sorted back to front, so they test against depth but don't write it.
+    const TRANSPARENT: SceneVariant = SceneVariant
+    {
+        depth_write: false,
+        blend: Blend::Alpha,
+        ..Self::OPAQUE
+    };
+
+    fn for_view_mode(view_mode: ViewMode) -> SceneVariant
+    {
+        match view_mode
+        {
+            ViewMode::Wireframe => SceneVariant
+            {
+                view_mode,
+                polygon_mode: vk::PolygonMode::LINE,
+                cull_mode: vk::CullModeFlags::NONE,
+                ..Self::OPAQUE
+            },
+            // Drawn over the shaded scene, so edges on visible faces pass at equal depth.
+            ViewMode::WireframeOverShaded => SceneVariant
+            {
+                view_mode,
+                polygon_mode: vk::PolygonMode::LINE,
+                cull_mode: vk::CullModeFlags::NONE,
+                depth_write: false,
+                depth_compare_op: vk::CompareOp::LESS_OR_EQUAL,
+                ..Self::OPAQUE
+            },
+            ViewMode::Overdraw => SceneVariant
+            {
+                view_mode,
+                cull_mode: vk::CullModeFlags::NONE,
+                depth_test: false,
+                depth_write: false,
+                blend: Blend::Additive,
+                ..Self::OPAQUE
+            },
+            _ => SceneVariant { view_mode, ..Self::OPAQUE },
+        }
+    }
+}
+
+pub unsafe fn create_pipeline(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> 
 {
     // Stages
     let vertex_shader_code = include_bytes!("../../assets/shaders/vert.spv");
-    let fragment_shader_code = include_bytes!("../../assets/shaders/frag.spv");
+    // The bindless variant indexes the texture array in set 1 instead of sampling binding 1.
+    let fragment_shader_code = if data.descriptor_indexing.is_enabled()
+    {
+        &include_bytes!("../../assets/shaders/frag_bindless.spv")[..]
+    }
+    else
+    {
+        &include_bytes!("../../assets/shaders/frag.spv")[..]
+    };
 
     let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
-    let fragment_shader_module = create_shader_module(device, &fragment_shader_code[..])?;
+    let fragment_shader_module = create_shader_module(device, fragment_shader_code)?;
 
+    let frag_push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+        .offset(0)
+        .size(4); // bindless texture index
+
+    // Layout
+    let set_layouts = if data.descriptor_indexing.is_enabled()
+    {
+        vec![data.frame_set_layout, data.bindless_set_layout, data.object_set_layout]
+    }
+    else
+    {
+        vec![data.frame_set_layout, data.material_set_layout, data.object_set_layout]
+    };
+    let push_constant_ranges = &[frag_push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(&set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+    set_object_name(instance, device, data.pipeline_layout, "pipeline_layout:main")?;
+    
+    // Create
+    let modules = (vertex_shader_module, fragment_shader_module);
+
+    data.pipeline = create_scene_pipeline(instance, device, data, modules, &SceneVariant::OPAQUE)?;
+    data.transparent_pipeline = create_scene_pipeline(instance, device, data, modules, &SceneVariant::TRANSPARENT)?;
+    set_object_name(instance, device, data.pipeline, "pipeline:main")?;
+    set_object_name(instance, device, data.transparent_pipeline, "pipeline:transparent")?;
+
+    // Debug variants of the main pipeline, one per view mode.
+    data.view_mode_pipelines.clear();
+    for view_mode in ViewMode::ALL
+    {
+        if view_mode == ViewMode::Shaded || !view_mode.is_supported(data)
+        {
+            continue;
+        }
+
+        let pipeline = create_scene_pipeline(instance, device, data, modules, &SceneVariant::for_view_mode(view_mode))?;
+        set_object_name(instance, device, pipeline, &format!("pipeline:view_mode:{}", view_mode.name()))?;
+        data.view_mode_pipelines.push((view_mode, pipeline));
+    }
+        
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    Ok(())
+}
+
+unsafe fn create_scene_pipeline(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    (vertex_shader_module, fragment_shader_module): (vk::ShaderModule, vk::ShaderModule),
+    variant: &SceneVariant,
+) -> Result<vk::Pipeline>
+{
+    // Stages
     let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
         .module(vertex_shader_module)
         .name(b"main\0");
 
+    // Selects the view mode branch in the fragment shader, and gives the depth
+    // view the clip planes.
+    let constants = [variant.view_mode.shader_mode().to_ne_bytes(), NEAR_PLANE.to_ne_bytes(), FAR_PLANE.to_ne_bytes()].concat();
+    let map_entries = &[
+        vk::SpecializationMapEntry { constant_id: 0, offset: 0, size: 4 },
+        vk::SpecializationMapEntry { constant_id: 1, offset: 4, size: 4 },
+        vk::SpecializationMapEntry { constant_id: 2, offset: 8, size: 4 },
+    ];
+    let specialization_info = vk::SpecializationInfo::builder()
+        .map_entries(map_entries)
+        .data(&constants);
+
     let fragment_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::FRAGMENT)
         .module(fragment_shader_module)
-        .name(b"main\0");
+        .name(b"main\0")
+        .specialization_info(&specialization_info);
 
     // Vertex Input State
     let vertex_binding_descriptions = &[Vertex::binding_description()];
@@ -37,31 +202,17 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
         .primitive_restart_enable(false);
 
-    // Viewport State
-    let viewport = vk::Viewport::builder()
-        .x(0.0)
-        .y(0.0)
-        .width(data.swapchain_extent.width as f32)
-        .height(data.swapchain_extent.height as f32)
-        .min_depth(0.0)
-        .max_depth(1.0);
-
-    let scissor = vk::Rect2D::builder()
-        .offset(vk::Offset2D { x: 0, y: 0})
-        .extent(data.swapchain_extent);
-
-    let viewports = &[viewport];
-    let scissors = &[scissor];
+    // Viewport State, set when recording so the pipeline outlives resizes
     let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
-        .viewports(viewports)
-        .scissors(scissors);
+        .viewport_count(1)
+        .scissor_count(1);
     
     let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
         .depth_clamp_enable(false)
         .rasterizer_discard_enable(false)
-        .polygon_mode(vk::PolygonMode::FILL)
+        .polygon_mode(variant.polygon_mode)
         .line_width(1.0)
-        .cull_mode(vk::CullModeFlags::BACK)
+        .cull_mode(variant.cull_mode)
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
         .depth_bias_enable(false);
 
@@ -75,24 +226,39 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
 
     //Depth Stencil State
     let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
-        .depth_test_enable(true)
-        .depth_write_enable(true)
-        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_test_enable(variant.depth_test)
+        .depth_write_enable(variant.depth_write)
+        .depth_compare_op(variant.depth_compare_op)
         .depth_bounds_test_enable(false)
         .min_depth_bounds(0.0)
         .max_depth_bounds(1.0)
         .stencil_test_enable(false);
 
     // Color Blend State
-    let attachment = vk::PipelineColorBlendAttachmentState::builder()
-        .color_write_mask(vk::ColorComponentFlags::all())
-        .blend_enable(true)
-        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
-        .color_blend_op(vk::BlendOp::ADD)
-        .src_alpha_blend_factor(vk::BlendFactor::ONE)
-        .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
-        .alpha_blend_op(vk::BlendOp::ADD);
+    let attachment = match variant.blend
+    {
+        Blend::Opaque => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::all())
+            .blend_enable(false),
+        Blend::Alpha => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::all())
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .alpha_blend_op(vk::BlendOp::ADD),
+        Blend::Additive => vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(vk::ColorComponentFlags::all())
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ONE)
+            .dst_color_blend_factor(vk::BlendFactor::ONE)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .alpha_blend_op(vk::BlendOp::ADD),
+    };
 
     let attachments = &[attachment];
     let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
@@ -103,33 +269,21 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
 
     let dynamic_states = &[
         vk::DynamicState::VIEWPORT,
+        vk::DynamicState::SCISSOR,
         vk::DynamicState::LINE_WIDTH,
     ];
     let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
         .dynamic_states(dynamic_states);
 
-    let vert_push_constant_range = vk::PushConstantRange::builder()
-        .stage_flags(vk::ShaderStageFlags::VERTEX)
-        .offset(0)
-        .size(64); // size of model matrix
-    
-    let frag_push_constant_range = vk::PushConstantRange::builder()
-        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
-        .offset(64)
-        .size(4);
-
-    // Layout
-    let set_layouts = &[data.descriptor_set_layout];
-    let push_constant_ranges = &[vert_push_constant_range, frag_push_constant_range];
-    let layout_info = vk::PipelineLayoutCreateInfo::builder()
-        .set_layouts(set_layouts)
-        .push_constant_ranges(push_constant_ranges);
-
-    data.pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
-    
     // Create
+    let colour_formats = &[HDR_FORMAT];
+    let depth_format = get_depth_format(instance, data)?;
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .color_attachment_formats(colour_formats)
+        .depth_attachment_format(depth_format);
+
     let stages = &[vertex_stage, fragment_stage];
-    let info = vk::GraphicsPipelineCreateInfo::builder()
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
         .stages(stages)
         .vertex_input_state(&vertex_input_state)
         .input_assembly_state(&input_assembly_state)
@@ -138,22 +292,22 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .multisample_state(&multisample_state)
         .depth_stencil_state(&depth_stencil_state)
         .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
         .layout(data.pipeline_layout)
         .render_pass(data.render_pass)
         .subpass(0);
 
-    data.pipeline = device
-        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
-        .0[0];
-        
-    // Cleanup
-    device.destroy_shader_module(vertex_shader_module, None);
-    device.destroy_shader_module(fragment_shader_module, None);
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
 
-    Ok(())
+    Ok(device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0])
 }
 
-unsafe fn create_shader_module(
+pub unsafe fn create_shader_module(
     device: &Device,
     bytecode: &[u8],
 ) -> Result<vk::ShaderModule> 
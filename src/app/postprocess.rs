@@ -0,0 +1,679 @@
+use std::mem::size_of;
+
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::{Config, PostEffect, TonemapOperator};
+
+use super::appdata::AppData;
+use super::hud;
+use super::debug_utils::{begin_label, end_label, set_object_name, PASS_LABEL_COLOUR};
+use super::pipeline::create_shader_module;
+use super::rendering::{begin_rendering, end_rendering, image_barrier};
+use super::texture::{create_image, create_image_view};
+
+/// Format of the scene target and of the intermediate post-process targets.
+pub const HDR_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+impl PostEffect
+{
+    fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::Tonemap => "tonemap",
+            Self::Gamma => "gamma",
+        }
+    }
+
+    fn fragment_shader(self) -> &'static [u8]
+    {
+        match self
+        {
+            Self::Tonemap => include_bytes!("../../assets/shaders/tonemap_frag.spv"),
+            Self::Gamma => include_bytes!("../../assets/shaders/gamma_frag.spv"),
+        }
+    }
+}
+
+/// Fragment push constants shared by all post effects.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct PostParameters
+{
+    exposure: f32,
+    tonemap_operator: u32,
+    gamma: f32,
+    // Set when the swapchain is sRGB and encodes on write.
+    srgb_target: u32,
+}
+
+/// The Vulkan objects of one post-process pass.
+#[derive(Clone, Debug)]
+pub struct PostPass
+{
+    pub effect: PostEffect,
+    pub render_pass: vk::RenderPass,
+    pub pipeline: vk::Pipeline,
+    // Output; null for the last pass, which writes to the swapchain.
+    pub image: vk::Image,
+    pub image_memory: vk::DeviceMemory,
+    pub image_view: vk::ImageView,
+    // One framebuffer, or one per swapchain image for the last pass.
+    pub framebuffers: Vec<vk::Framebuffer>,
+    // Samples the output of the previous pass (or the scene).
+    pub descriptor_set: vk::DescriptorSet,
+}
+
+/// Objects that do not depend on the swapchain.
+pub unsafe fn create_post_layouts(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    let input_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+    let bindings = &[input_binding];
+    let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(bindings);
+
+    data.post_descriptor_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+        .offset(0)
+        .size(size_of::<PostParameters>() as u32);
+
+    let set_layouts = &[data.post_descriptor_set_layout];
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.post_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+
+    // The passes read texel for texel.
+    let sampler_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::NEAREST)
+        .min_filter(vk::Filter::NEAREST)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(0.0);
+
+    data.post_sampler = device.create_sampler(&sampler_info, None)?;
+
+    set_object_name(instance, device, data.post_descriptor_set_layout, "descriptor_set_layout:post")?;
+    set_object_name(instance, device, data.post_pipeline_layout, "pipeline_layout:post")?;
+    set_object_name(instance, device, data.post_sampler, "sampler:post")?;
+
+    Ok(())
+}
+
+/// Creates the passes of the chain, their targets and descriptor sets.
+pub unsafe fn create_post_passes(
+    instance: &Instance,
+    device: &Device,
+    config: &Config,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Something has to write to the swapchain.
+    let effects = if config.post_chain.is_empty()
+    {
+        vec![PostEffect::Gamma]
+    }
+    else
+    {
+        config.post_chain.clone()
+    };
+
+    data.post_passes.clear();
+
+    for (index, effect) in effects.iter().enumerate()
+    {
+        let last = index == effects.len() - 1;
+        let name = format!("post[{}]:{}", index, effect.name());
+
+        let format = if last { data.swapchain_format } else { HDR_FORMAT };
+        let render_pass = if data.rendering.is_dynamic()
+        {
+            vk::RenderPass::null()
+        }
+        else
+        {
+            create_post_render_pass(device, format, last)?
+        };
+        let pipeline = create_post_pipeline(device, data, render_pass, format, *effect)?;
+
+        if !render_pass.is_null()
+        {
+            set_object_name(instance, device, render_pass, &format!("render_pass:{}", name))?;
+        }
+        set_object_name(instance, device, pipeline, &format!("pipeline:{}", name))?;
+
+        data.post_passes.push(PostPass
+        {
+            effect: *effect,
+            render_pass,
+            pipeline,
+            image: vk::Image::null(),
+            image_memory: vk::DeviceMemory::null(),
+            image_view: vk::ImageView::null(),
+            framebuffers: vec![],
+            descriptor_set: vk::DescriptorSet::null(),
+        });
+    }
+
+    create_post_targets(instance, device, data)
+}
+
+/// The intermediate images, framebuffers and input descriptors, which follow
+/// the swapchain extent while the render passes and pipelines don't.
+pub unsafe fn create_post_targets(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Descriptor Pool
+
+    let count = data.post_passes.len() as u32;
+    let sampler_size = vk::DescriptorPoolSize::builder()
+        .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(count);
+
+    let pool_sizes = &[sampler_size];
+    let create_info = vk::DescriptorPoolCreateInfo::builder()
+        .pool_sizes(pool_sizes)
+        .max_sets(count);
+
+    data.post_descriptor_pool = device.create_descriptor_pool(&create_info, None)?;
+    set_object_name(instance, device, data.post_descriptor_pool, "descriptor_pool:post")?;
+
+    // Targets
+
+    let mut input = data.hdr_image_view;
+
+    for index in 0..data.post_passes.len()
+    {
+        let last = index == data.post_passes.len() - 1;
+        let name = format!("post[{}]:{}", index, data.post_passes[index].effect.name());
+
+        let (image, image_memory, image_view) = if last
+        {
+            (vk::Image::null(), vk::DeviceMemory::null(), vk::ImageView::null())
+        }
+        else
+        {
+            let (image, image_memory) = create_image(
+                instance,
+                device,
+                data,
+                data.swapchain_extent.width,
+                data.swapchain_extent.height,
+                1,
+                vk::SampleCountFlags::_1,
+                HDR_FORMAT,
+                vk::ImageTiling::OPTIMAL,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            )?;
+
+            let image_view = create_image_view(device, image, HDR_FORMAT, vk::ImageAspectFlags::COLOR, 1)?;
+
+            set_object_name(instance, device, image, &name)?;
+            set_object_name(instance, device, image_view, &format!("{}_view", name))?;
+
+            (image, image_memory, image_view)
+        };
+
+        // Dynamic rendering targets the views directly when recording.
+        let targets = if data.rendering.is_dynamic()
+        {
+            vec![]
+        }
+        else if last
+        {
+            data.swapchain_image_views.clone()
+        }
+        else
+        {
+            vec![image_view]
+        };
+
+        let render_pass = data.post_passes[index].render_pass;
+        let framebuffers = targets
+            .iter()
+            .map(|v|
+            {
+                let attachments = &[*v];
+                let create_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(attachments)
+                    .width(data.swapchain_extent.width)
+                    .height(data.swapchain_extent.height)
+                    .layers(1);
+
+                device.create_framebuffer(&create_info, None)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let set_layouts = &[data.post_descriptor_set_layout];
+        let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(data.post_descriptor_pool)
+            .set_layouts(set_layouts);
+
+        let descriptor_set = device.allocate_descriptor_sets(&allocate_info)?[0];
+        write_input(device, data, descriptor_set, input);
+
+        set_object_name(instance, device, descriptor_set, &format!("descriptor_set:{}", name))?;
+        for (i, framebuffer) in framebuffers.iter().enumerate()
+        {
+            set_object_name(instance, device, *framebuffer, &format!("framebuffer:{}[{}]", name, i))?;
+        }
+
+        let pass = &mut data.post_passes[index];
+        pass.image = image;
+        pass.image_memory = image_memory;
+        pass.image_view = image_view;
+        pass.framebuffers = framebuffers;
+        pass.descriptor_set = descriptor_set;
+
+        input = image_view;
+    }
+
+    Ok(())
+}
+
+unsafe fn write_input(
+    device: &Device,
+    data: &AppData,
+    descriptor_set: vk::DescriptorSet,
+    input: vk::ImageView,
+)
+{
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(input)
+        .sampler(data.post_sampler);
+
+    let image_info = &[info];
+    let write = vk::WriteDescriptorSet::builder()
+        .dst_set(descriptor_set)
+        .dst_binding(0)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(image_info);
+
+    device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+}
+
+unsafe fn create_post_render_pass(
+    device: &Device,
+    format: vk::Format,
+    last: bool,
+) -> Result<vk::RenderPass>
+{
+    // Every texel is overwritten, so the old contents don't matter.
+    let colour_attachment = vk::AttachmentDescription::builder()
+        .format(format)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(if last { vk::ImageLayout::PRESENT_SRC_KHR } else { vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL });
+
+    let colour_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+    let colour_attachments = &[colour_attachment_reference];
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(colour_attachments);
+
+    // The input has to be written, and our target no longer read, before we draw.
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+            | vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+            | vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::SHADER_READ);
+
+    let attachments = &[colour_attachment];
+    let subpasses = &[subpass];
+    let dependencies = &[dependency];
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+
+    Ok(device.create_render_pass(&create_info, None)?)
+}
+
+unsafe fn create_post_pipeline(
+    device: &Device,
+    data: &AppData,
+    render_pass: vk::RenderPass,
+    format: vk::Format,
+    effect: PostEffect,
+) -> Result<vk::Pipeline>
+{
+    // Stages
+    let vertex_shader_code = include_bytes!("../../assets/shaders/fullscreen_vert.spv");
+
+    let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
+    let fragment_shader_module = create_shader_module(device, effect.fragment_shader())?;
+
+    let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vertex_shader_module)
+        .name(b"main\0");
+
+    let fragment_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(fragment_shader_module)
+        .name(b"main\0");
+
+    // A single triangle covering the screen, generated from `gl_VertexIndex`.
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State, set when recording so the pipeline outlives resizes
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(false);
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(false);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+
+    // Create
+    let colour_formats = &[format];
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .color_attachment_formats(colour_formats);
+
+    let stages = &[vertex_stage, fragment_stage];
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(data.post_pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
+
+    let pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0];
+
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    Ok(pipeline)
+}
+
+/// Records the chain, reading the resolved scene and ending in the swapchain image.
+pub unsafe fn record_post_passes(
+    app: &super::App,
+    image_index: usize,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()>
+{
+    let parameters = PostParameters
+    {
+        exposure: app.config.exposure,
+        tonemap_operator: match app.config.tonemap_operator
+        {
+            TonemapOperator::Reinhard => 0,
+            TonemapOperator::Aces => 1,
+        },
+        gamma: app.config.gamma,
+        srgb_target: is_srgb(app.data.swapchain_format) as u32,
+    };
+
+    let (_, parameter_bytes, _) = std::slice::from_ref(&parameters).align_to::<u8>();
+
+    let render_area = vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(app.data.swapchain_extent);
+
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(app.data.swapchain_extent.width as f32)
+        .height(app.data.swapchain_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    for (index, pass) in app.data.post_passes.iter().enumerate()
+    {
+        let last = index == app.data.post_passes.len() - 1;
+
+        begin_label(&app.instance, command_buffer, pass.effect.name(), PASS_LABEL_COLOUR)?;
+
+        if app.data.rendering.is_dynamic()
+        {
+            let (image, image_view) = if last
+            {
+                (app.data.swapchain_images[image_index], app.data.swapchain_image_views[image_index])
+            }
+            else
+            {
+                (pass.image, pass.image_view)
+            };
+
+            // The input has to be written, and our target no longer read, before we draw.
+            image_barrier(
+                &app.device,
+                command_buffer,
+                image,
+                vk::ImageAspectFlags::COLOR,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::empty(),
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            );
+
+            // Every texel is overwritten, so the old contents don't matter.
+            let colour_attachment = vk::RenderingAttachmentInfo::builder()
+                .image_view(image_view)
+                .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                .load_op(vk::AttachmentLoadOp::DONT_CARE)
+                .store_op(vk::AttachmentStoreOp::STORE);
+
+            let colour_attachments = &[colour_attachment];
+            let info = vk::RenderingInfo::builder()
+                .render_area(render_area)
+                .layer_count(1)
+                .color_attachments(colour_attachments);
+
+            begin_rendering(&app.device, &app.data, command_buffer, &info);
+        }
+        else
+        {
+            let framebuffer = if pass.framebuffers.len() == 1
+            {
+                pass.framebuffers[0]
+            }
+            else
+            {
+                pass.framebuffers[image_index]
+            };
+
+            let info = vk::RenderPassBeginInfo::builder()
+                .render_pass(pass.render_pass)
+                .framebuffer(framebuffer)
+                .render_area(render_area);
+
+            app.device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+        }
+
+        app.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pass.pipeline);
+        app.device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+        app.device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+        app.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            app.data.post_pipeline_layout,
+            0,
+            &[pass.descriptor_set],
+            &[],
+        );
+        app.device.cmd_push_constants(
+            command_buffer,
+            app.data.post_pipeline_layout,
+            vk::ShaderStageFlags::FRAGMENT,
+            0,
+            parameter_bytes,
+        );
+        app.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+
+        // The overlay goes on top of the final image, after tonemapping.
+        if last && app.hud_visible
+        {
+            hud::record_hud(app, image_index, command_buffer)?;
+        }
+
+        if app.data.rendering.is_dynamic()
+        {
+            end_rendering(&app.device, &app.data, command_buffer);
+
+            let (image, new_layout, dst_stage_mask, dst_access_mask) = if last
+            {
+                (
+                    app.data.swapchain_images[image_index],
+                    vk::ImageLayout::PRESENT_SRC_KHR,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    vk::AccessFlags::empty(),
+                )
+            }
+            else
+            {
+                (
+                    pass.image,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    vk::AccessFlags::SHADER_READ,
+                )
+            };
+
+            image_barrier(
+                &app.device,
+                command_buffer,
+                image,
+                vk::ImageAspectFlags::COLOR,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                new_layout,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_stage_mask,
+                dst_access_mask,
+            );
+        }
+        else
+        {
+            app.device.cmd_end_render_pass(command_buffer);
+        }
+
+        end_label(&app.instance, command_buffer);
+    }
+
+    Ok(())
+}
+
+pub fn is_srgb(format: vk::Format) -> bool
+{
+    matches!(format, vk::Format::B8G8R8A8_SRGB | vk::Format::R8G8B8A8_SRGB)
+}
+
+pub unsafe fn destroy_post_targets(device: &Device, data: &mut AppData)
+{
+    for pass in &mut data.post_passes
+    {
+        pass.framebuffers.drain(..).for_each(|f| device.destroy_framebuffer(f, None));
+        device.destroy_image_view(pass.image_view, None);
+        device.free_memory(pass.image_memory, None);
+        device.destroy_image(pass.image, None);
+    }
+
+    device.destroy_descriptor_pool(data.post_descriptor_pool, None);
+}
+
+/// Destroys the pipelines and render passes; `destroy_post_targets` has to
+/// have run first, as part of `destroy_swapchain`.
+pub unsafe fn destroy_post_passes(device: &Device, data: &mut AppData)
+{
+    for pass in data.post_passes.drain(..)
+    {
+        device.destroy_pipeline(pass.pipeline, None);
+        device.destroy_render_pass(pass.render_pass, None);
+    }
+}
+
+pub unsafe fn destroy_post_layouts(device: &Device, data: &AppData)
+{
+    device.destroy_sampler(data.post_sampler, None);
+    device.destroy_pipeline_layout(data.post_pipeline_layout, None);
+    device.destroy_descriptor_set_layout(data.post_descriptor_set_layout, None);
+}
@@ -0,0 +1,286 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::ptr::copy_nonoverlapping as memcpy;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, Result};
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::{Config, RecordFormat};
+
+use super::buffer::create_buffer;
+use super::debug_utils::set_object_name;
+use super::rendering::image_barrier;
+
+/// Frames that can wait for the writer before rendering blocks on it.
+const QUEUED_FRAMES: usize = 4;
+
+/// A swapchain image copied out by a frame, waiting for the frame's fence.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Capture
+{
+    pub index: u64,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+}
+
+/// The pixels of one captured frame, on their way to the writer thread.
+struct CapturedFrame
+{
+    index: u64,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// Records every presented frame and hands the pixels to a writer thread, so
+/// neither the GPU nor the render loop waits on the disk. The clock steps
+/// `1 / fps` seconds per frame while recording.
+#[derive(Debug)]
+pub struct Recorder
+{
+    pub fps: u32,
+    // Frames captured so far, numbering the files.
+    pub frame_index: u64,
+    format: RecordFormat,
+    // Size of the first frame, which every frame of a raw stream has to match.
+    extent: Option<vk::Extent2D>,
+    stopped: bool,
+    sender: Option<SyncSender<CapturedFrame>>,
+    writer: Option<JoinHandle<Result<()>>>,
+}
+
+impl Recorder
+{
+    pub fn new(config: &Config, output: &str) -> Result<Self>
+    {
+        let (sender, receiver) = mpsc::sync_channel(QUEUED_FRAMES);
+        let format = config.record_format;
+        let output = output.to_string();
+
+        // Fail on an unwritable output before the first frame, not in the writer.
+        let sink: Box<dyn Write + Send> = match format
+        {
+            RecordFormat::Png =>
+            {
+                fs::create_dir_all(&output)?;
+                Box::new(io::sink())
+            },
+            RecordFormat::Raw if output == "-" => Box::new(io::stdout()),
+            RecordFormat::Raw => Box::new(File::create(&output)?),
+        };
+
+        let writer = thread::Builder::new()
+            .name("recorder".into())
+            .spawn(move || write_frames(receiver, format, PathBuf::from(output), BufWriter::new(sink)))?;
+
+        info!("Recording at {} FPS ({:?}).", config.record_fps, format);
+
+        Ok(Self
+        {
+            fps: config.record_fps.max(1),
+            frame_index: 0,
+            format,
+            extent: None,
+            stopped: false,
+            sender: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    /// Waits for the writer to flush every frame handed to it.
+    pub fn finish(&mut self) -> Result<()>
+    {
+        self.sender.take();
+        match self.writer.take()
+        {
+            Some(writer) => writer.join().map_err(|_| anyhow!("The recorder thread panicked."))?,
+            None => Ok(()),
+        }
+    }
+}
+
+fn write_frames(
+    receiver: Receiver<CapturedFrame>,
+    format: RecordFormat,
+    directory: PathBuf,
+    mut stream: BufWriter<Box<dyn Write + Send>>,
+) -> Result<()>
+{
+    for frame in receiver
+    {
+        match format
+        {
+            RecordFormat::Png =>
+            {
+                let path = directory.join(format!("frame_{:06}.png", frame.index));
+                let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), frame.width, frame.height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.write_header()?.write_image_data(&frame.pixels)?;
+            },
+            RecordFormat::Raw => stream.write_all(&frame.pixels)?,
+        }
+    }
+
+    stream.flush()?;
+
+    Ok(())
+}
+
+/// Copies the swapchain image into this frame's readback buffer, after the
+/// post-processing and HUD have drawn into it.
+pub unsafe fn record_capture(
+    app: &mut super::App,
+    image_index: usize,
+    command_buffer: vk::CommandBuffer,
+) -> Result<()>
+{
+    let Some(recorder) = app.recorder.as_mut() else { return Ok(()) };
+    if !app.data.swapchain_capture || recorder.stopped
+    {
+        return Ok(());
+    }
+
+    // A raw stream has no frame headers, so a frame of another size would corrupt it.
+    let extent = app.data.swapchain_extent;
+    let first = *recorder.extent.get_or_insert(extent);
+    if recorder.format == RecordFormat::Raw && first != extent
+    {
+        warn!(
+            "Resized from {}x{} to {}x{} while recording a raw stream, recording stopped.",
+            first.width, first.height, extent.width, extent.height,
+        );
+        recorder.stopped = true;
+        return Ok(());
+    }
+
+    let index = recorder.frame_index;
+    recorder.frame_index += 1;
+
+    // Sized to the swapchain; a resize replaces it once the frame is idle again.
+    let size = extent.width as u64 * extent.height as u64 * 4;
+    let frame = &mut app.data.frames[app.frame];
+    if frame.record_buffer_size != size
+    {
+        app.device.free_memory(frame.record_buffer_memory, None);
+        app.device.destroy_buffer(frame.record_buffer, None);
+
+        let (buffer, buffer_memory) = create_buffer(
+            &app.instance,
+            &app.device,
+            &app.data,
+            size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+        set_object_name(&app.instance, &app.device, buffer, &format!("readback:record[{}]", app.frame))?;
+
+        let frame = &mut app.data.frames[app.frame];
+        frame.record_buffer = buffer;
+        frame.record_buffer_memory = buffer_memory;
+        frame.record_buffer_size = size;
+    }
+
+    let image = app.data.swapchain_images[image_index];
+
+    image_barrier(
+        &app.device,
+        command_buffer,
+        image,
+        vk::ImageAspectFlags::COLOR,
+        vk::ImageLayout::PRESENT_SRC_KHR,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::AccessFlags::TRANSFER_READ,
+    );
+
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D { width: extent.width, height: extent.height, depth: 1 });
+
+    let frame = &mut app.data.frames[app.frame];
+    app.device.cmd_copy_image_to_buffer(
+        command_buffer,
+        image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        frame.record_buffer,
+        &[region],
+    );
+
+    // Presentation needs no access mask, the semaphore orders it after this.
+    image_barrier(
+        &app.device,
+        command_buffer,
+        image,
+        vk::ImageAspectFlags::COLOR,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        vk::ImageLayout::PRESENT_SRC_KHR,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::AccessFlags::empty(),
+        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        vk::AccessFlags::empty(),
+    );
+
+    // Makes the copy visible to the host once the frame's fence signals.
+    let barrier = vk::MemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::HOST_READ);
+
+    app.device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::HOST,
+        vk::DependencyFlags::empty(),
+        &[barrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[] as &[vk::ImageMemoryBarrier],
+    );
+
+    frame.record_pending = Some(Capture { index, extent, format: app.data.swapchain_format });
+
+    Ok(())
+}
+
+/// Hands the capture the frame recorded last time to the writer; its fence
+/// has signaled by the time the frame is recorded again.
+pub unsafe fn resolve_capture(app: &mut super::App, frame: usize) -> Result<()>
+{
+    let frame = &mut app.data.frames[frame];
+    let (Some(capture), Some(recorder)) = (frame.record_pending.take(), app.recorder.as_ref()) else { return Ok(()) };
+
+    let size = capture.extent.width as usize * capture.extent.height as usize * 4;
+    let memory = app.device.map_memory(frame.record_buffer_memory, 0, size as u64, vk::MemoryMapFlags::empty())?;
+    let mut pixels = vec![0u8; size];
+    memcpy(memory.cast(), pixels.as_mut_ptr(), size);
+    app.device.unmap_memory(frame.record_buffer_memory);
+
+    // The surface formats we pick are 8-bit BGRA or RGBA.
+    if matches!(capture.format, vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM)
+    {
+        pixels.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+    }
+
+    let frame = CapturedFrame { index: capture.index, width: capture.extent.width, height: capture.extent.height, pixels };
+    if let Some(sender) = &recorder.sender
+    {
+        sender.send(frame).map_err(|_| anyhow!("The recorder thread stopped."))?;
+    }
+
+    Ok(())
+}
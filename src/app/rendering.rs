@@ -0,0 +1,168 @@
+use anyhow::Result;
+
+use vulkanalia::vk::{DeviceV1_3, InstanceV1_1, KhrDynamicRenderingExtension};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::Config;
+
+use super::appdata::AppData;
+use super::physical_device::supports_extension;
+
+/// How passes are begun and ended.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RenderingPath
+{
+    /// `VkRenderPass` and `VkFramebuffer` objects.
+    #[default]
+    RenderPass,
+    /// `vkCmdBeginRendering` from Vulkan 1.3.
+    Core,
+    /// `vkCmdBeginRenderingKHR` from `VK_KHR_dynamic_rendering`.
+    Extension,
+}
+
+impl RenderingPath
+{
+    pub fn is_dynamic(self) -> bool
+    {
+        self != Self::RenderPass
+    }
+}
+
+/// Highest instance version we ask for, so 1.3 devices can use core dynamic rendering.
+pub fn instance_api_version(entry: &Entry) -> u32
+{
+    let version = entry.version().unwrap_or(vulkanalia::Version::V1_0_0);
+    vk::make_version(version.major, version.minor, 0).min(vk::make_version(1, 3, 0))
+}
+
+/// Picks dynamic rendering when it is enabled in the config and the device has it.
+///
+/// The extension path needs 1.2, which made its dependencies (`VK_KHR_create_renderpass2`,
+/// `VK_KHR_depth_stencil_resolve`) core.
+pub unsafe fn select_rendering_path(
+    instance: &Instance,
+    config: &Config,
+    data: &AppData,
+) -> Result<RenderingPath>
+{
+    if !config.dynamic_rendering
+    {
+        return Ok(RenderingPath::RenderPass);
+    }
+
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    let version = properties.api_version.min(data.api_version);
+
+    let path = if version >= vk::make_version(1, 3, 0)
+    {
+        RenderingPath::Core
+    }
+    else if version >= vk::make_version(1, 2, 0)
+        && supports_extension(instance, data.physical_device, &vk::KHR_DYNAMIC_RENDERING_EXTENSION.name)?
+    {
+        RenderingPath::Extension
+    }
+    else
+    {
+        info!("Dynamic rendering is not available, using render passes.");
+        return Ok(RenderingPath::RenderPass);
+    };
+
+    let mut dynamic_rendering = vk::PhysicalDeviceDynamicRenderingFeatures::builder();
+    let mut features = vk::PhysicalDeviceFeatures2::builder()
+        .push_next(&mut dynamic_rendering);
+    instance.get_physical_device_features2(data.physical_device, &mut features);
+
+    if dynamic_rendering.dynamic_rendering != vk::TRUE
+    {
+        info!("Dynamic rendering feature is not supported, using render passes.");
+        return Ok(RenderingPath::RenderPass);
+    }
+
+    info!("Using dynamic rendering ({:?}).", path);
+    Ok(path)
+}
+
+pub unsafe fn begin_rendering(
+    device: &Device,
+    data: &AppData,
+    command_buffer: vk::CommandBuffer,
+    info: &vk::RenderingInfo,
+)
+{
+    match data.rendering
+    {
+        RenderingPath::Core => device.cmd_begin_rendering(command_buffer, info),
+        RenderingPath::Extension => device.cmd_begin_rendering_khr(command_buffer, info),
+        RenderingPath::RenderPass => unreachable!("begin_rendering on the render pass path"),
+    }
+}
+
+pub unsafe fn end_rendering(
+    device: &Device,
+    data: &AppData,
+    command_buffer: vk::CommandBuffer,
+)
+{
+    match data.rendering
+    {
+        RenderingPath::Core => device.cmd_end_rendering(command_buffer),
+        RenderingPath::Extension => device.cmd_end_rendering_khr(command_buffer),
+        RenderingPath::RenderPass => unreachable!("end_rendering on the render pass path"),
+    }
+}
+
+/// A layout transition that stands in for a render pass attachment description
+/// and its external subpass dependency.
+pub unsafe fn image_barrier(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    aspects: vk::ImageAspectFlags,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    src_stage_mask: vk::PipelineStageFlags,
+    src_access_mask: vk::AccessFlags,
+    dst_stage_mask: vk::PipelineStageFlags,
+    dst_access_mask: vk::AccessFlags,
+)
+{
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(aspects)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        src_stage_mask,
+        dst_stage_mask,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+}
+
+/// Aspects to transition for a depth format, including stencil if it has any.
+pub fn depth_aspects(format: vk::Format) -> vk::ImageAspectFlags
+{
+    match format
+    {
+        vk::Format::D32_SFLOAT_S8_UINT | vk::Format::D24_UNORM_S8_UINT =>
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+        _ => vk::ImageAspectFlags::DEPTH,
+    }
+}
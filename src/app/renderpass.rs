@@ -3,6 +3,8 @@ use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
+use super::debug_utils::set_object_name;
+use super::postprocess::HDR_FORMAT;
 
 
 
@@ -12,8 +14,14 @@ pub unsafe fn create_render_pass(
     data: &mut AppData,
 ) -> Result<()> 
 {
+    // Dynamic rendering describes the attachments when recording instead.
+    if data.rendering.is_dynamic()
+    {
+        return Ok(());
+    }
+
     let colour_attachment = vk::AttachmentDescription::builder()
-        .format(data.swapchain_format)
+        .format(HDR_FORMAT)
         .samples(data.msaa_samples)
         .load_op(vk::AttachmentLoadOp::CLEAR)
         .store_op(vk::AttachmentStoreOp::STORE)
@@ -33,14 +41,14 @@ pub unsafe fn create_render_pass(
         .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
 
     let colour_resolve_attachment = vk::AttachmentDescription::builder()
-        .format(data.swapchain_format)
+        .format(HDR_FORMAT)
         .samples(vk::SampleCountFlags::_1)
         .load_op(vk::AttachmentLoadOp::DONT_CARE)
         .store_op(vk::AttachmentStoreOp::STORE)
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+        .final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
 
     let colour_attachment_reference = vk::AttachmentReference::builder()
         .attachment(0)
@@ -63,29 +71,41 @@ pub unsafe fn create_render_pass(
         .depth_stencil_attachment(&depth_stencil_attachment_reference)
         .resolve_attachments(resolve_attachments);
 
+    // Also waits for the previous frame's post-processing to stop sampling the resolve target.
     let dependency = vk::SubpassDependency::builder()
         .src_subpass(vk::SUBPASS_EXTERNAL)
         .dst_subpass(0)
         .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-            | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+            | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+            | vk::PipelineStageFlags::FRAGMENT_SHADER)
         .src_access_mask(vk::AccessFlags::empty())
         .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
             | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
         .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE
             | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
 
+    // The resolved scene is sampled by the first post-process pass.
+    let after = vk::SubpassDependency::builder()
+        .src_subpass(0)
+        .dst_subpass(vk::SUBPASS_EXTERNAL)
+        .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ);
+
     let attachments = &[
         colour_attachment, 
         depth_stencil_attachment,
         colour_resolve_attachment];
     let subpasses = &[subpass];
-    let dependencies = &[dependency];
+    let dependencies = &[dependency, after];
     let create_info = vk::RenderPassCreateInfo::builder()
         .attachments(attachments) 
         .subpasses(subpasses)
         .dependencies(dependencies);
 
     data.render_pass = device.create_render_pass(&create_info, None)?;
+    set_object_name(instance, device, data.render_pass, "render_pass:main")?;
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::mem::size_of;
+use std::ptr::{self, copy_nonoverlapping as memcpy};
+
+use anyhow::{anyhow, Result};
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::appdata::AppData;
+use super::buffer::create_buffer;
+use super::debug_utils::set_object_name;
+
+/// Space each frame gets for its uniform data.
+pub const RING_REGION_SIZE: u64 = 64 * 1024;
+
+/// One persistently mapped uniform buffer split into a region per frame.
+///
+/// Values are bump allocated inside the current region and bound with
+/// dynamic offsets, so nothing is mapped or unmapped while rendering.
+#[derive(Clone, Debug)]
+pub struct RingBuffer
+{
+    pub buffer: vk::Buffer,
+    pub memory: vk::DeviceMemory,
+    mapped: *mut u8,
+    alignment: u64,
+    regions: usize,
+    region: usize,
+    head: u64,
+}
+
+impl Default for RingBuffer
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            buffer: vk::Buffer::null(),
+            memory: vk::DeviceMemory::null(),
+            mapped: ptr::null_mut(),
+            alignment: 1,
+            regions: 0,
+            region: 0,
+            head: 0,
+        }
+    }
+}
+
+impl RingBuffer
+{
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        data: &AppData,
+        regions: usize,
+    ) -> Result<Self>
+    {
+        let size = RING_REGION_SIZE * regions as u64;
+        let (buffer, memory) = create_buffer(
+            instance,
+            device,
+            data,
+            size,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+
+        set_object_name(instance, device, buffer, "uniform_ring")?;
+
+        let mapped = device.map_memory(memory, 0, size, vk::MemoryMapFlags::empty())?.cast();
+        let alignment = instance
+            .get_physical_device_properties(data.physical_device)
+            .limits
+            .min_uniform_buffer_offset_alignment;
+
+        Ok(Self { buffer, memory, mapped, alignment, regions, region: 0, head: 0 })
+    }
+
+    /// Starts writing into the region of a frame whose previous use has finished on the GPU.
+    pub fn begin_region(&mut self, region: usize)
+    {
+        self.region = region % self.regions.max(1);
+        self.head = 0;
+    }
+
+    /// Copies a value into the current region and returns its dynamic offset.
+    pub unsafe fn push<T: Copy>(&mut self, value: &T) -> Result<u32>
+    {
+        let offset = self.head.next_multiple_of(self.alignment);
+        let end = offset + size_of::<T>() as u64;
+        if end > RING_REGION_SIZE
+        {
+            return Err(anyhow!("Uniform ring region is full ({} bytes).", RING_REGION_SIZE));
+        }
+
+        let offset = self.region as u64 * RING_REGION_SIZE + offset;
+        memcpy(value, self.mapped.add(offset as usize).cast(), 1);
+        self.head = end;
+
+        Ok(offset as u32)
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device)
+    {
+        if !self.mapped.is_null()
+        {
+            device.unmap_memory(self.memory);
+            self.mapped = ptr::null_mut();
+        }
+
+        device.free_memory(self.memory, None);
+        device.destroy_buffer(self.buffer, None);
+    }
+}
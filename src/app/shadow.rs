@@ -0,0 +1,322 @@
+use anyhow::Result;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::Config;
+
+use super::appdata::AppData;
+use super::debug_utils::set_object_name;
+use super::depth_objects::get_supported_format;
+use super::pipeline::create_shader_module;
+use super::texture::{create_image, create_image_view};
+use super::vertices::Vertex;
+
+/// Depth format of the shadow map, which also has to be sampled in the main pass.
+pub unsafe fn get_shadow_format(
+    instance: &Instance,
+    data: &AppData,
+) -> Result<vk::Format>
+{
+    let candidates = &[
+        vk::Format::D32_SFLOAT,
+        vk::Format::D16_UNORM,
+    ];
+
+    get_supported_format(
+        instance,
+        data,
+        candidates,
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+    )
+}
+
+pub unsafe fn create_shadow_render_pass(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    if data.rendering.is_dynamic()
+    {
+        return Ok(());
+    }
+
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(get_shadow_format(instance, data)?)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+
+    let depth_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .depth_stencil_attachment(&depth_attachment_reference);
+
+    // Previous frame's main pass has to be done sampling before we write again.
+    let before = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .src_access_mask(vk::AccessFlags::SHADER_READ)
+        .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+            | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+    // Depth writes have to land before the main pass samples the shadow map.
+    let after = vk::SubpassDependency::builder()
+        .src_subpass(0)
+        .dst_subpass(vk::SUBPASS_EXTERNAL)
+        .src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ);
+
+    let attachments = &[depth_attachment];
+    let subpasses = &[subpass];
+    let dependencies = &[before, after];
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+
+    data.shadow_render_pass = device.create_render_pass(&create_info, None)?;
+    set_object_name(instance, device, data.shadow_render_pass, "render_pass:shadow")?;
+
+    Ok(())
+}
+
+pub unsafe fn create_shadow_objects(
+    instance: &Instance,
+    device: &Device,
+    config: &Config,
+    data: &mut AppData,
+) -> Result<()>
+{
+    let format = get_shadow_format(instance, data)?;
+    data.shadow_format = format;
+    data.shadow_extent = vk::Extent2D { width: config.shadow_map_size, height: config.shadow_map_size };
+
+    // Image
+
+    let (shadow_image, shadow_image_memory) = create_image(
+        instance,
+        device,
+        data,
+        data.shadow_extent.width,
+        data.shadow_extent.height,
+        1,
+        vk::SampleCountFlags::_1,
+        format,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.shadow_image = shadow_image;
+    data.shadow_image_memory = shadow_image_memory;
+
+    data.shadow_image_view = create_image_view(
+        device,
+        data.shadow_image,
+        format,
+        vk::ImageAspectFlags::DEPTH,
+        1,
+    )?;
+
+    // Sampler
+
+    // Comparison sampler so `sampler2DShadow` lookups return the lit fraction.
+    let sampler_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .unnormalized_coordinates(false)
+        .compare_enable(true)
+        .compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(1.0);
+
+    data.shadow_sampler = device.create_sampler(&sampler_info, None)?;
+
+    set_object_name(instance, device, data.shadow_image, "shadow_map")?;
+    set_object_name(instance, device, data.shadow_image_view, "shadow_map_view")?;
+    set_object_name(instance, device, data.shadow_sampler, "sampler:shadow")?;
+
+    // Framebuffer
+
+    if !data.rendering.is_dynamic()
+    {
+        let attachments = &[data.shadow_image_view];
+        let framebuffer_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(data.shadow_render_pass)
+            .attachments(attachments)
+            .width(data.shadow_extent.width)
+            .height(data.shadow_extent.height)
+            .layers(1);
+
+        data.shadow_framebuffer = device.create_framebuffer(&framebuffer_info, None)?;
+        set_object_name(instance, device, data.shadow_framebuffer, "framebuffer:shadow")?;
+    }
+
+    Ok(())
+}
+
+pub unsafe fn create_shadow_pipeline(
+    instance: &Instance,
+    device: &Device,
+    config: &Config,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Stages
+
+    // Depth only, so there is no fragment stage.
+    let vertex_shader_code = include_bytes!("../../assets/shaders/shadow_vert.spv");
+    let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
+
+    let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vertex_shader_module)
+        .name(b"main\0");
+
+    // Vertex Input State
+    let vertex_binding_descriptions = &[Vertex::binding_description()];
+    let vertex_attribute_descriptions = &Vertex::attribute_descriptions();
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(vertex_binding_descriptions)
+        .vertex_attribute_descriptions(vertex_attribute_descriptions);
+
+    // Input Assembly State
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(data.shadow_extent.width as f32)
+        .height(data.shadow_extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    let scissor = vk::Rect2D::builder()
+        .offset(vk::Offset2D { x: 0, y: 0 })
+        .extent(data.shadow_extent);
+
+    let viewports = &[viewport];
+    let scissors = &[scissor];
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(viewports)
+        .scissors(scissors);
+
+    // The room is seen from inside, so both faces cast shadows.
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(true)
+        .depth_bias_constant_factor(config.shadow_depth_bias_constant)
+        .depth_bias_clamp(0.0)
+        .depth_bias_slope_factor(config.shadow_depth_bias_slope);
+
+    // Multisample State
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(vk::SampleCountFlags::_1);
+
+    // Depth Stencil State
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    // Color Blend State
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(&[] as &[vk::PipelineColorBlendAttachmentState])
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    let vert_push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX)
+        .offset(0)
+        .size(64); // size of model matrix
+
+    // Layout
+    let set_layouts = &[data.frame_set_layout];
+    let push_constant_ranges = &[vert_push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.shadow_pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+    set_object_name(instance, device, data.shadow_pipeline_layout, "pipeline_layout:shadow")?;
+
+    // Create
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .depth_attachment_format(get_shadow_format(instance, data)?);
+
+    let stages = &[vertex_stage];
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(data.shadow_pipeline_layout)
+        .render_pass(data.shadow_render_pass)
+        .subpass(0);
+
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
+
+    data.shadow_pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0];
+    set_object_name(instance, device, data.shadow_pipeline, "pipeline:shadow")?;
+
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+
+    Ok(())
+}
+
+pub unsafe fn destroy_shadow_objects(device: &Device, data: &AppData)
+{
+    device.destroy_pipeline(data.shadow_pipeline, None);
+    device.destroy_pipeline_layout(data.shadow_pipeline_layout, None);
+    device.destroy_framebuffer(data.shadow_framebuffer, None);
+    device.destroy_sampler(data.shadow_sampler, None);
+    device.destroy_image_view(data.shadow_image_view, None);
+    device.free_memory(data.shadow_image_memory, None);
+    device.destroy_image(data.shadow_image, None);
+    device.destroy_render_pass(data.shadow_render_pass, None);
+}
@@ -0,0 +1,396 @@
+use std::f32::consts::PI;
+use std::fs::File;
+use std::path::Path;
+use std::ptr::copy_nonoverlapping as memcpy;
+
+use anyhow::{anyhow, Result};
+
+use nalgebra_glm as glm;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::config::Config;
+
+use super::appdata::AppData;
+use super::buffer::create_buffer;
+use super::debug_utils::set_object_name;
+use super::depth_objects::get_depth_format;
+use super::pipeline::create_shader_module;
+use super::texture::{copy_buffer_to_image, create_layered_image, create_layered_image_view, transition_image_layout};
+
+/// File names of the faces in a skybox directory, in Vulkan layer order (+X, -X, +Y, -Y, +Z, -Z).
+const FACE_FILES: [&str; 6] = ["px.png", "nx.png", "py.png", "ny.png", "pz.png", "nz.png"];
+/// Face size used when the faces are generated rather than loaded.
+const GENERATED_FACE_SIZE: u32 = 512;
+
+/// RGBA8 pixels of the six cube faces, one after the other.
+struct CubeFaces
+{
+    size: u32,
+    pixels: Vec<u8>,
+}
+
+pub unsafe fn create_skybox_image(
+    instance: &Instance,
+    device: &Device,
+    config: &Config,
+    data: &mut AppData,
+) -> Result<()>
+{
+    let faces = match &config.skybox
+    {
+        Some(path) if Path::new(path).is_dir() => load_faces(Path::new(path))?,
+        Some(path) => load_equirectangular(Path::new(path))?,
+        None => generate_gradient(GENERATED_FACE_SIZE),
+    };
+
+    let size = faces.pixels.len() as u64;
+
+    let (staging_buffer, staging_buffer_memory) = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+    memcpy(faces.pixels.as_ptr(), memory.cast(), faces.pixels.len());
+    device.unmap_memory(staging_buffer_memory);
+
+    let (skybox_image, skybox_image_memory) = create_layered_image(
+        instance,
+        device,
+        data,
+        faces.size,
+        faces.size,
+        1,
+        6,
+        vk::ImageCreateFlags::CUBE_COMPATIBLE,
+        vk::SampleCountFlags::_1,
+        vk::Format::R8G8B8A8_SRGB,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    data.skybox_image = skybox_image;
+    data.skybox_image_memory = skybox_image_memory;
+
+    transition_image_layout(
+        device,
+        data,
+        data.skybox_image,
+        vk::Format::R8G8B8A8_SRGB,
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        1,
+        6,
+    )?;
+
+    copy_buffer_to_image(device, data, staging_buffer, data.skybox_image, faces.size, faces.size, 6)?;
+
+    transition_image_layout(
+        device,
+        data,
+        data.skybox_image,
+        vk::Format::R8G8B8A8_SRGB,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        1,
+        6,
+    )?;
+
+    device.destroy_buffer(staging_buffer, None);
+    device.free_memory(staging_buffer_memory, None);
+
+    data.skybox_image_view = create_layered_image_view(
+        device,
+        data.skybox_image,
+        vk::Format::R8G8B8A8_SRGB,
+        vk::ImageAspectFlags::COLOR,
+        1,
+        vk::ImageViewType::CUBE,
+        6,
+    )?;
+
+    let sampler_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+        .min_lod(0.0)
+        .max_lod(1.0);
+
+    data.skybox_sampler = device.create_sampler(&sampler_info, None)?;
+
+    set_object_name(instance, device, data.skybox_image, "texture:skybox")?;
+    set_object_name(instance, device, data.skybox_image_view, "texture_view:skybox")?;
+    set_object_name(instance, device, data.skybox_sampler, "sampler:skybox")?;
+
+    Ok(())
+}
+
+pub unsafe fn create_skybox_pipeline(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()>
+{
+    // Stages
+    let vertex_shader_code = include_bytes!("../../assets/shaders/skybox_vert.spv");
+    let fragment_shader_code = include_bytes!("../../assets/shaders/skybox_frag.spv");
+
+    let vertex_shader_module = create_shader_module(device, &vertex_shader_code[..])?;
+    let fragment_shader_module = create_shader_module(device, &fragment_shader_code[..])?;
+
+    let vertex_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(vertex_shader_module)
+        .name(b"main\0");
+
+    let fragment_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(fragment_shader_module)
+        .name(b"main\0");
+
+    // The cube is generated from `gl_VertexIndex`, so there is no vertex input.
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+
+    // Input Assembly State
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State, set when recording so the pipeline outlives resizes
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+
+    // Seen from the inside.
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(false);
+
+    // Multisample State
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(false)
+        .rasterization_samples(data.msaa_samples);
+
+    // The sky sits at depth 1.0, so it only fills what the scene left at the clear depth.
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(false)
+        .depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    // Color Blend State
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(false);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+
+    // Create
+    let colour_formats = &[super::postprocess::HDR_FORMAT];
+    let mut rendering_info = vk::PipelineRenderingCreateInfo::builder()
+        .color_attachment_formats(colour_formats)
+        .depth_attachment_format(get_depth_format(instance, data)?);
+
+    let stages = &[vertex_stage, fragment_stage];
+    let mut info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(data.pipeline_layout)
+        .render_pass(data.render_pass)
+        .subpass(0);
+
+    if data.rendering.is_dynamic()
+    {
+        info = info.push_next(&mut rendering_info);
+    }
+
+    data.skybox_pipeline = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
+        .0[0];
+    set_object_name(instance, device, data.skybox_pipeline, "pipeline:skybox")?;
+
+    // Cleanup
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    Ok(())
+}
+
+pub unsafe fn destroy_skybox_image(device: &Device, data: &AppData)
+{
+    device.destroy_sampler(data.skybox_sampler, None);
+    device.destroy_image_view(data.skybox_image_view, None);
+    device.free_memory(data.skybox_image_memory, None);
+    device.destroy_image(data.skybox_image, None);
+}
+
+/// Loads `px.png` .. `nz.png` from a directory.
+fn load_faces(directory: &Path) -> Result<CubeFaces>
+{
+    let mut size = 0;
+    let mut pixels = vec![];
+
+    for file in FACE_FILES
+    {
+        let (width, height, face) = load_rgba(&directory.join(file))?;
+        if width != height || (size != 0 && width != size)
+        {
+            return Err(anyhow!("Skybox faces must be square and the same size ({}).", file));
+        }
+
+        size = width;
+        pixels.extend_from_slice(&face);
+    }
+
+    Ok(CubeFaces { size, pixels })
+}
+
+/// Projects an equirectangular (latitude/longitude) image onto the six faces.
+fn load_equirectangular(path: &Path) -> Result<CubeFaces>
+{
+    let (width, height, source) = load_rgba(path)?;
+    let size = (width / 4).max(1);
+
+    Ok(build_faces(size, |direction|
+    {
+        let longitude = direction.y.atan2(direction.x);
+        let latitude = direction.z.clamp(-1.0, 1.0).asin();
+
+        let u = 0.5 + longitude / (2.0 * PI);
+        let v = 0.5 - latitude / PI;
+
+        let x = ((u * width as f32) as u32).min(width - 1);
+        let y = ((v * height as f32) as u32).min(height - 1);
+        let offset = ((y * width + x) * 4) as usize;
+
+        [source[offset], source[offset + 1], source[offset + 2], 255]
+    }))
+}
+
+/// Simple sky used when no skybox is configured.
+fn generate_gradient(size: u32) -> CubeFaces
+{
+    let horizon = glm::vec3(0.75, 0.85, 0.95);
+    let zenith = glm::vec3(0.25, 0.45, 0.85);
+    let ground = glm::vec3(0.3, 0.28, 0.25);
+
+    build_faces(size, |direction|
+    {
+        let colour = if direction.z >= 0.0
+        {
+            glm::lerp(&horizon, &zenith, direction.z.sqrt())
+        }
+        else
+        {
+            glm::lerp(&horizon, &ground, (-direction.z * 4.0).min(1.0))
+        };
+
+        // The image is sRGB, so store the gradient with an approximate gamma.
+        let encode = |c: f32| (c.powf(1.0 / 2.2) * 255.0) as u8;
+        [encode(colour.x), encode(colour.y), encode(colour.z), 255]
+    })
+}
+
+/// Fills each face texel with `sample(direction)`, where direction is in world space (z up).
+fn build_faces(size: u32, sample: impl Fn(glm::Vec3) -> [u8; 4]) -> CubeFaces
+{
+    let mut pixels = Vec::with_capacity((size * size * 4 * 6) as usize);
+
+    for face in 0..6
+    {
+        for y in 0..size
+        {
+            for x in 0..size
+            {
+                let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                pixels.extend_from_slice(&sample(cube_to_world(face_direction(face, u, v))));
+            }
+        }
+    }
+
+    CubeFaces { size, pixels }
+}
+
+/// Direction through texel (u, v) of a cube face, following the Vulkan cube map face table.
+fn face_direction(face: u32, u: f32, v: f32) -> glm::Vec3
+{
+    let direction = match face
+    {
+        0 => glm::vec3(1.0, -v, -u),
+        1 => glm::vec3(-1.0, -v, u),
+        2 => glm::vec3(u, 1.0, v),
+        3 => glm::vec3(u, -1.0, -v),
+        4 => glm::vec3(u, -v, 1.0),
+        _ => glm::vec3(-u, -v, -1.0),
+    };
+
+    glm::normalize(&direction)
+}
+
+/// Cube maps are y up, the scene is z up; must match `skybox.frag`.
+fn cube_to_world(direction: glm::Vec3) -> glm::Vec3
+{
+    glm::vec3(direction.x, -direction.z, direction.y)
+}
+
+fn load_rgba(path: &Path) -> Result<(u32, u32, Vec<u8>)>
+{
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type
+    {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        png::ColorType::Indexed => return Err(anyhow!("Unexpanded indexed image `{}`.", path.display())),
+    };
+
+    Ok((info.width, info.height, pixels))
+}
@@ -0,0 +1,12 @@
+/// Counters gathered while recording a frame.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameStats
+{
+    // Mesh draws that passed the frustum test, and the ones skipped.
+    pub visible: usize,
+    pub culled: usize,
+    // Triangles in the LODs drawn by the main pass.
+    pub triangles: usize,
+    // Smoothed time between frames, in seconds.
+    pub frame_time: f32,
+}
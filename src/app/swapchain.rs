@@ -4,6 +4,7 @@ use anyhow::{ Result, Ok};
 use winit::window::Window;
 
 use super::appdata::AppData; 
+use super::debug_utils::set_object_name;
 use super::queue_family_indices::QueueFamilyIndices;
 use super::texture::create_image_view;
 
@@ -46,11 +47,28 @@ pub unsafe fn create_swapchain(
     let support = SwapChainSupport::get(instance, data, data.physical_device)?;
 
     let surface_format = get_swapchain_surface_format(&support.formats);
-    let present_mode = get_swapchain_present_mode(&support.present_modes);
+    let present_mode = get_swapchain_present_mode(&support.present_modes, data.requested_present_mode);
     let extent = get_swapchain_extent(window, support.capabilities);
 
     data.swapchain_format = surface_format.format;
     data.swapchain_extent = extent;
+    data.present_mode = present_mode;
+    data.present_modes = support.present_modes.clone();
+
+    // Recording copies the presented images out.
+    let mut usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+    if data.swapchain_capture
+    {
+        if support.capabilities.supported_usage_flags.contains(vk::ImageUsageFlags::TRANSFER_SRC)
+        {
+            usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+        }
+        else
+        {
+            warn!("The surface can't be copied from, recording is disabled.");
+            data.swapchain_capture = false;
+        }
+    }
 
     let image_count = if support.capabilities.max_image_count != 0 && support.capabilities.min_image_count + 1 > support.capabilities.max_image_count {
         support.capabilities.max_image_count
@@ -74,22 +92,32 @@ pub unsafe fn create_swapchain(
         .image_color_space(surface_format.color_space)
         .image_extent(extent)
         .image_array_layers(1)
-        .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+        .image_usage(usage)
         .image_sharing_mode(image_sharing_mode)
         .queue_family_indices(&queue_family_indices)
         .pre_transform(support.capabilities.current_transform)
         .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
         .present_mode(present_mode)
         .clipped(true)
-        .old_swapchain(vk::SwapchainKHR::null());
+        // Lets the driver hand resources over from the swapchain being replaced.
+        .old_swapchain(data.swapchain);
 
+    let old_swapchain = data.swapchain;
     data.swapchain = device.create_swapchain_khr(&info, None)?;
+    device.destroy_swapchain_khr(old_swapchain, None);
     data.swapchain_images = device.get_swapchain_images_khr(data.swapchain)?;
 
+    set_object_name(instance, device, data.swapchain, "swapchain")?;
+    for (i, image) in data.swapchain_images.iter().enumerate()
+    {
+        set_object_name(instance, device, *image, &format!("swapchain[{}]", i))?;
+    }
+
     Ok(())
 }
 
 pub unsafe fn create_swapchain_image_views(
+    instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()> 
@@ -100,6 +128,11 @@ pub unsafe fn create_swapchain_image_views(
         .map(|i| create_image_view(device, *i, data.swapchain_format, vk::ImageAspectFlags::COLOR, 1))
         .collect::<Result<Vec<_>, _>>()?;
 
+    for (i, view) in data.swapchain_image_views.iter().enumerate()
+    {
+        set_object_name(instance, device, *view, &format!("swapchain_view[{}]", i))?;
+    }
+
     Ok(())
 }
 
@@ -107,26 +140,59 @@ pub fn get_swapchain_surface_format(
     formats: &[vk::SurfaceFormatKHR],
 ) -> vk::SurfaceFormatKHR 
 {
-    formats
+    // The gamma pass encodes the output itself, so prefer a linear (UNORM) swapchain.
+    let preferred = &[
+        vk::Format::B8G8R8A8_UNORM,
+        vk::Format::R8G8B8A8_UNORM,
+        vk::Format::B8G8R8A8_SRGB,
+    ];
+
+    preferred
         .iter()
-        .cloned()
-        .find(|f| {
-            f.format == vk::Format::B8G8R8A8_SRGB
-                && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR  
-        })
+        .find_map(|p| formats
+            .iter()
+            .cloned()
+            .find(|f| f.format == *p && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR))
         .unwrap_or_else(|| formats[0])
 }
 
+/// The present modes in the order F2 cycles through them.
+pub const PRESENT_MODES: [vk::PresentModeKHR; 4] = [
+    vk::PresentModeKHR::FIFO,
+    vk::PresentModeKHR::FIFO_RELAXED,
+    vk::PresentModeKHR::MAILBOX,
+    vk::PresentModeKHR::IMMEDIATE,
+];
+
+/// Picks the requested present mode, or FIFO (which is always supported) if
+/// the surface doesn't offer it.
 pub fn get_swapchain_present_mode(
     present_modes: &[vk::PresentModeKHR],
+    requested: vk::PresentModeKHR,
 ) -> vk::PresentModeKHR 
 {
-    present_modes
-        .iter()
-        .cloned()
-        .find(|m| {
-            *m == vk::PresentModeKHR::MAILBOX  
-        })
+    if present_modes.contains(&requested)
+    {
+        requested
+    }
+    else
+    {
+        warn!("Present mode {:?} is not supported, falling back to FIFO.", requested);
+        vk::PresentModeKHR::FIFO
+    }
+}
+
+/// The supported present mode after `current` in `PRESENT_MODES`.
+pub fn next_present_mode(
+    current: vk::PresentModeKHR,
+    present_modes: &[vk::PresentModeKHR],
+) -> vk::PresentModeKHR
+{
+    let start = PRESENT_MODES.iter().position(|m| *m == current).unwrap_or(0);
+
+    (1..=PRESENT_MODES.len())
+        .map(|i| PRESENT_MODES[(start + i) % PRESENT_MODES.len()])
+        .find(|m| present_modes.contains(m))
         .unwrap_or(vk::PresentModeKHR::FIFO)
 }
 
@@ -135,7 +201,7 @@ pub fn get_swapchain_extent(
     capabilities: vk::SurfaceCapabilitiesKHR,
 ) -> vk::Extent2D 
 {
-    if capabilities.current_extent.width != u32::max_value() 
+    if capabilities.current_extent.width != u32::MAX 
     {
         capabilities.current_extent
     } else
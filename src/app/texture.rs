@@ -4,7 +4,7 @@ use std::ptr::copy_nonoverlapping as memcpy;
 use anyhow::{Result, Ok, anyhow};
 use vulkanalia::{prelude::v1_0::*, vk::BufferMemoryBarrier};
 
-use super::{appdata::AppData, buffer::create_buffer, vertices::get_memory_type_index, commands::{begin_single_time_commands, end_single_time_commands}};
+use super::{appdata::AppData, buffer::create_buffer, debug_utils::set_object_name, vertices::get_memory_type_index, commands::{begin_single_time_commands, end_single_time_commands}};
 
 pub unsafe fn create_texture_image(
     instance: &Instance,
@@ -62,6 +62,9 @@ pub unsafe fn create_texture_image(
     data.texture_image = texture_image;
     data.texture_image_memory = texture_image_memory;
 
+    set_object_name(instance, device, staging_buffer, "staging:viking_room")?;
+    set_object_name(instance, device, data.texture_image, "texture:viking_room")?;
+
     transition_image_layout(
         device, 
         data, 
@@ -70,6 +73,7 @@ pub unsafe fn create_texture_image(
         vk::ImageLayout::UNDEFINED, 
         vk::ImageLayout::TRANSFER_DST_OPTIMAL,
         data.mip_levels,
+        1,
     )?;
 
     copy_buffer_to_image(
@@ -79,6 +83,7 @@ pub unsafe fn create_texture_image(
         data.texture_image, 
         width, 
         height,
+        1,
     )?;
 
     device.destroy_buffer(staging_buffer, None);
@@ -240,7 +245,81 @@ unsafe fn generate_mipmaps(
     Ok(())
 }
 
+/// Uploads tightly packed pixels into a sampled image with a single mip level.
+pub unsafe fn create_texture_from_pixels(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    format: vk::Format,
+    name: &str,
+) -> Result<(vk::Image, vk::DeviceMemory)>
+{
+    let size = pixels.len() as u64;
+
+    let (staging_buffer, staging_buffer_memory) = create_buffer(
+        instance, 
+        device, 
+        data, 
+        size, vk::BufferUsageFlags::TRANSFER_SRC, 
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+    memcpy(pixels.as_ptr(), memory.cast(), pixels.len());
+    device.unmap_memory(staging_buffer_memory);
+
+    let (image, image_memory) = create_image(
+        instance, 
+        device, 
+        data, 
+        width, 
+        height, 
+        1,
+        vk::SampleCountFlags::_1,
+        format, 
+        vk::ImageTiling::OPTIMAL, 
+        vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST, 
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    set_object_name(instance, device, staging_buffer, &format!("staging:{}", name))?;
+    set_object_name(instance, device, image, &format!("texture:{}", name))?;
+
+    transition_image_layout(
+        device, 
+        data, 
+        image, 
+        format, 
+        vk::ImageLayout::UNDEFINED, 
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        1,
+        1,
+    )?;
+
+    copy_buffer_to_image(device, data, staging_buffer, image, width, height, 1)?;
+
+    transition_image_layout(
+        device, 
+        data, 
+        image, 
+        format, 
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL, 
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        1,
+        1,
+    )?;
+
+    device.destroy_buffer(staging_buffer, None);
+    device.free_memory(staging_buffer_memory, None);
+
+    Ok((image, image_memory))
+}
+
 pub unsafe fn create_texture_image_view(
+    instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()>
@@ -252,6 +331,8 @@ pub unsafe fn create_texture_image_view(
         vk::ImageAspectFlags::COLOR,
         data.mip_levels,
     )?;
+
+    set_object_name(instance, device, data.texture_image_view, "texture_view:viking_room")?;
     
     Ok(())
 }
@@ -263,17 +344,39 @@ pub unsafe fn create_image_view(
     aspects: vk::ImageAspectFlags,
     mip_levels: u32,
 ) -> Result<vk::ImageView>
+{
+    create_layered_image_view(
+        device,
+        image,
+        format,
+        aspects,
+        mip_levels,
+        vk::ImageViewType::_2D,
+        1,
+    )
+}
+
+/// Creates a view over several array layers, e.g. a cube (6 layers) or a 2D array.
+pub unsafe fn create_layered_image_view(
+    device: &Device,
+    image: vk::Image,
+    format: vk::Format,
+    aspects: vk::ImageAspectFlags,
+    mip_levels: u32,
+    view_type: vk::ImageViewType,
+    layer_count: u32,
+) -> Result<vk::ImageView>
 {
     let subresource_range = vk::ImageSubresourceRange::builder()
         .aspect_mask(aspects)
         .base_mip_level(0)
         .level_count(mip_levels)
         .base_array_layer(0)
-        .layer_count(1);
+        .layer_count(layer_count);
 
     let create_info = vk::ImageViewCreateInfo::builder()
         .image(image)
-        .view_type(vk::ImageViewType::_2D)
+        .view_type(view_type)
         .format(format)
         .subresource_range(subresource_range);
 
@@ -281,6 +384,7 @@ pub unsafe fn create_image_view(
 }
 
 pub unsafe fn create_texture_sampler(
+    instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()>
@@ -303,6 +407,7 @@ pub unsafe fn create_texture_sampler(
         .max_lod(data.mip_levels as f32);
 
     data.texture_sampler = device.create_sampler(&create_info, None)?;
+    set_object_name(instance, device, data.texture_sampler, "sampler:texture")?;
     
     Ok(())
 }
@@ -315,6 +420,7 @@ pub unsafe fn transition_image_layout(
     old_layout: vk::ImageLayout,
     new_layout: vk::ImageLayout,
     mip_levels: u32,
+    layer_count: u32,
 ) -> Result<()>
 {
     let (
@@ -365,7 +471,7 @@ pub unsafe fn transition_image_layout(
         .base_mip_level(0)
         .level_count(mip_levels)
         .base_array_layer(0)
-        .layer_count(1);
+        .layer_count(layer_count);
 
     let barrier = vk::ImageMemoryBarrier::builder()
         .old_layout(old_layout)
@@ -393,13 +499,15 @@ pub unsafe fn transition_image_layout(
     Ok(())
 }
 
-unsafe fn copy_buffer_to_image(
+/// Copies tightly packed layers (e.g. the six faces of a cube) from a buffer.
+pub unsafe fn copy_buffer_to_image(
     device: &Device,
     data: &AppData,
     buffer: vk::Buffer,
     image: vk::Image,
     width: u32,
     height: u32,
+    layer_count: u32,
 ) -> Result<()>
 {
     let command_buffer = begin_single_time_commands(device, data)?;
@@ -408,7 +516,7 @@ unsafe fn copy_buffer_to_image(
         .aspect_mask(vk::ImageAspectFlags::COLOR)
         .mip_level(0)
         .base_array_layer(0)
-        .layer_count(1);
+        .layer_count(layer_count);
 
     let region = vk::BufferImageCopy::builder()
         .buffer_offset(0)
@@ -443,10 +551,45 @@ pub unsafe fn create_image(
     usage: vk::ImageUsageFlags,
     properties: vk::MemoryPropertyFlags,
 ) -> Result<(vk::Image, vk::DeviceMemory)> 
+{
+    create_layered_image(
+        instance,
+        device,
+        data,
+        width,
+        height,
+        mip_levels,
+        1,
+        vk::ImageCreateFlags::empty(),
+        samples,
+        format,
+        tiling,
+        usage,
+        properties,
+    )
+}
+
+/// Creates a 2D image with several array layers; cubes need `CUBE_COMPATIBLE` and 6 layers.
+pub unsafe fn create_layered_image(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+    array_layers: u32,
+    flags: vk::ImageCreateFlags,
+    samples: vk::SampleCountFlags,
+    format: vk::Format,
+    tiling: vk::ImageTiling,
+    usage: vk::ImageUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<(vk::Image, vk::DeviceMemory)> 
 {
     // Image 
 
     let create_info = vk::ImageCreateInfo::builder()
+        .flags(flags)
         .image_type(vk::ImageType::_2D)
         .extent(vk::Extent3D {
             width,
@@ -454,7 +597,7 @@ pub unsafe fn create_image(
             depth: 1,
         })
         .mip_levels(mip_levels)
-        .array_layers(1)
+        .array_layers(array_layers)
         .format(format)
         .tiling(tiling)
         .initial_layout(vk::ImageLayout::UNDEFINED)
@@ -1,13 +1,15 @@
-use std::{mem::size_of, time::Instant};
+use std::mem::size_of;
 
 use nalgebra_glm as glm;
 
 use vulkanalia::prelude::v1_0::*;
 
 use anyhow::{Ok, Result};
-use std::ptr::copy_nonoverlapping as memcpy;
 
-use super::{appdata::AppData, buffer::create_buffer};
+use super::{appdata::AppData, debug_utils::set_object_name};
+use super::camera::OrbitCamera;
+use super::ring_buffer::RingBuffer;
+use super::MAX_FRAMES_IN_FLIGHT;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
@@ -15,156 +17,265 @@ pub struct UniformBufferObject
 {
     pub view: glm::Mat4,
     pub proj: glm::Mat4,
+    pub light_view_proj: glm::Mat4,
+    pub light_direction: glm::Vec4,
 }
 
-pub unsafe fn update_uniform_buffer(image_index: usize, start: &Instant, data: &AppData, device: &Device) -> Result<()>
+/// Per-object data in set 2.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ObjectData
+{
+    pub model: glm::Mat4,
+    pub opacity: f32,
+    // 0 normally, up to 1 for the selected model.
+    pub highlight: f32,
+}
+
+/// Direction the directional light shines in (world space, z up).
+pub fn light_direction() -> glm::Vec3
 {
-    let time = start.elapsed().as_secs_f32();
+    glm::normalize(&glm::vec3(-0.4, -0.6, -1.0))
+}
 
+/// Orthographic view-projection of the directional light, covering the whole scene.
+pub fn light_view_proj() -> glm::Mat4
+{
     let view = glm::look_at(
-        &glm::vec3(6.0, 2.0, 2.0), 
-        &glm::vec3(0.0, 0.0, 0.0), 
+        &(-light_direction() * 10.0),
+        &glm::vec3(0.0, 0.0, 0.0),
         &glm::vec3(0.0, 0.0, 1.0),
     );
 
+    let mut proj = glm::ortho_rh_zo(-4.0, 4.0, -4.0, 4.0, 0.1, 20.0);
+    proj[(1, 1)] *= -1.0;
+
+    proj * view
+}
+
+/// Clip planes of `projection_matrix`, also handed to the depth view mode.
+pub const NEAR_PLANE: f32 = 0.1;
+pub const FAR_PLANE: f32 = 10.0;
+
+pub fn projection_matrix(extent: vk::Extent2D) -> glm::Mat4
+{
     let mut proj = glm::perspective_rh_zo(
-        data.swapchain_extent.width as f32 / data.swapchain_extent.height as f32, 
+        extent.width as f32 / extent.height as f32, 
         glm::radians(&glm::vec1(45.0))[0], 
-        0.1, 
-        10.0,
+        NEAR_PLANE, 
+        FAR_PLANE,
     );
     proj[(1, 1)] *= -1.0;
 
-    let ubo = UniformBufferObject { view, proj };
+    proj
+}
 
-    let memory = device.map_memory(
-        data.uniform_buffers_memory[image_index], 
-        0, 
-        size_of::<UniformBufferObject>() as u64, 
-        vk::MemoryMapFlags::empty(),
-    )?;
+/// Writes this frame's camera and light into the ring and returns the dynamic offset for set 0.
+pub unsafe fn update_uniform_buffer(data: &mut AppData, camera: &OrbitCamera) -> Result<u32>
+{
+    let view = camera.view_matrix();
+    let proj = projection_matrix(data.swapchain_extent);
 
-    memcpy(&ubo, memory.cast(), 1);
+    let light_view_proj = light_view_proj();
+    let light_direction = glm::vec3_to_vec4(&light_direction());
 
-    device.unmap_memory(data.uniform_buffers_memory[image_index]);
+    let ubo = UniformBufferObject { view, proj, light_view_proj, light_direction };
 
-    Ok(())
+    data.uniform_ring.push(&ubo)
 }
 
-pub unsafe fn create_uniform_buffers(
+pub unsafe fn create_uniform_ring(
     instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()>
 {
-    data.uniform_buffers.clear();
-    data.uniform_buffers_memory.clear();
-
-    for _ in 0..data.swapchain_images.len()
-    {
-        let (uniform_buffer, uniform_buffer_memory) = create_buffer(
-            instance, 
-            device, 
-            data, 
-            size_of::<UniformBufferObject>() as u64, 
-            vk::BufferUsageFlags::UNIFORM_BUFFER, 
-            vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-        )?;
-
-        data.uniform_buffers.push(uniform_buffer);
-        data.uniform_buffers_memory.push(uniform_buffer_memory);
-    }
-    
+    data.uniform_ring = RingBuffer::create(instance, device, data, MAX_FRAMES_IN_FLIGHT)?;
     Ok(())
 }
 
-pub unsafe fn create_descriptor_set_layout(
+/// Combined image samplers in set 0, the shadow map and the skybox.
+pub const FRAME_SET_SAMPLERS: u32 = 2;
+
+/// Set 0 changes per frame, set 1 per material and set 2 per object.
+pub unsafe fn create_descriptor_set_layouts(
+    instance: &Instance,
     device: &Device,
     data: &mut AppData,
 ) -> Result<()>
 {
+    // Frame
+
     let ubo_binding = vk::DescriptorSetLayoutBinding::builder()
         .binding(0)
-        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
         .descriptor_count(1)
-        .stage_flags(vk::ShaderStageFlags::VERTEX);
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
 
-    let sampler_binding = vk::DescriptorSetLayoutBinding::builder()
+    let shadow_map_binding = vk::DescriptorSetLayoutBinding::builder()
         .binding(1)
         .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
         .descriptor_count(1)
         .stage_flags(vk::ShaderStageFlags::FRAGMENT);
-    
-    let bindings = &[ubo_binding, sampler_binding];
+
+    let skybox_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(2)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+    let bindings = &[ubo_binding, shadow_map_binding, skybox_binding];
     let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
         .bindings(bindings);
     
-    data.descriptor_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+    data.frame_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+    set_object_name(instance, device, data.frame_set_layout, "descriptor_set_layout:frame")?;
 
-    Ok(())
-}
+    // Material
 
-pub unsafe fn create_descriptor_pool(device: &Device, data: &mut AppData) -> Result<()>
-{
-    let ubo_size = vk::DescriptorPoolSize::builder()
-        .type_(vk::DescriptorType::UNIFORM_BUFFER)
-        .descriptor_count(data.swapchain_images.len() as u32);
+    let albedo_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
 
-    let sampler_size = vk::DescriptorPoolSize::builder()
-        .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .descriptor_count(data.swapchain_images.len() as u32);
-    
-    let pool_sizes = &[ubo_size, sampler_size];
-    let create_info = vk::DescriptorPoolCreateInfo::builder()
-        .pool_sizes(pool_sizes)
-        .max_sets(data.swapchain_images.len() as u32);
+    let bindings = &[albedo_binding];
+    let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(bindings);
+
+    data.material_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+    set_object_name(instance, device, data.material_set_layout, "descriptor_set_layout:material")?;
+
+    // Object
+
+    let object_binding = vk::DescriptorSetLayoutBinding::builder()
+        .binding(0)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+
+    let bindings = &[object_binding];
+    let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+        .bindings(bindings);
+
+    data.object_set_layout = device.create_descriptor_set_layout(&create_info, None)?;
+    set_object_name(instance, device, data.object_set_layout, "descriptor_set_layout:object")?;
 
-    data.descriptor_pool = device.create_descriptor_pool(&create_info, None)?;
     Ok(())
 }
 
-pub unsafe fn create_descriptor_sets(device: &Device, data: &mut AppData)
-    -> Result<()>
+/// What a frame's set 0 and set 2 were last written with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameBindings
 {
-    let set_layouts = vec![data.descriptor_set_layout; data.swapchain_images.len()];
-    let allocate_info = vk::DescriptorSetAllocateInfo::builder()
-        .descriptor_pool(data.descriptor_pool) 
-        .set_layouts(&set_layouts);
-    
-    data.descriptor_sets = device.allocate_descriptor_sets(&allocate_info)?;
+    pub uniform_buffer: vk::Buffer,
+    pub shadow_map: vk::ImageView,
+    pub skybox: vk::ImageView,
+}
+
+/// Allocates this frame's set 0 and set 2 the first time it is recorded, and
+/// rewrites only the bindings whose resources changed since. Both point at the
+/// uniform ring, and the dynamic offsets pick out the frame's and each object's data.
+pub unsafe fn update_descriptor_sets(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+    frame: usize,
+) -> Result<()>
+{
+    let context = &mut data.frames[frame];
+    if context.frame_set == vk::DescriptorSet::null()
+    {
+        context.frame_set = context.descriptor_allocator.allocate(instance, device, data.frame_set_layout)?;
+        context.object_set = context.descriptor_allocator.allocate(instance, device, data.object_set_layout)?;
+    }
+
+    let (frame_set, object_set) = (context.frame_set, context.object_set);
+    let written = context.bindings;
+    let bindings = FrameBindings
+    {
+        uniform_buffer: data.uniform_ring.buffer,
+        shadow_map: data.shadow_image_view,
+        skybox: data.skybox_image_view,
+    };
+
+    if bindings == written
+    {
+        return Ok(());
+    }
+
+    // Frame
+
+    let info = vk::DescriptorBufferInfo::builder()
+        .buffer(data.uniform_ring.buffer)
+        .offset(0)
+        .range(size_of::<UniformBufferObject>() as u64);
+        
+    let buffer_info = &[info];
+    let ubo_write = vk::WriteDescriptorSet::builder()
+        .dst_set(frame_set)
+        .dst_binding(0)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+        .buffer_info(buffer_info);
+
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL)
+        .image_view(data.shadow_image_view)
+        .sampler(data.shadow_sampler);
+
+    let shadow_map_info = &[info];
+    let shadow_map_write = vk::WriteDescriptorSet::builder()
+        .dst_set(frame_set)
+        .dst_binding(1)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(shadow_map_info);
+
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(data.skybox_image_view)
+        .sampler(data.skybox_sampler);
+
+    let skybox_info = &[info];
+    let skybox_write = vk::WriteDescriptorSet::builder()
+        .dst_set(frame_set)
+        .dst_binding(2)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(skybox_info);
 
-    for i in 0..data.swapchain_images.len()
+    // Object
+
+    let info = vk::DescriptorBufferInfo::builder()
+        .buffer(data.uniform_ring.buffer)
+        .offset(0)
+        .range(size_of::<ObjectData>() as u64);
+
+    let object_info = &[info];
+    let object_write = vk::WriteDescriptorSet::builder()
+        .dst_set(object_set)
+        .dst_binding(0)
+        .dst_array_element(0)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+        .buffer_info(object_info);
+
+    let mut writes = vec![];
+    if bindings.uniform_buffer != written.uniform_buffer
+    {
+        writes.extend([ubo_write, object_write]);
+    }
+    if bindings.shadow_map != written.shadow_map
     {
-        let info = vk::DescriptorBufferInfo::builder()
-            .buffer(data.uniform_buffers[i])
-            .offset(0)
-            .range(size_of::<UniformBufferObject>() as u64);
-            
-        let buffer_info = &[info];
-        let ubo_write = vk::WriteDescriptorSet::builder()
-            .dst_set(data.descriptor_sets[i])
-            .dst_binding(0)
-            .dst_array_element(0)
-            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-            .buffer_info(buffer_info);
-
-        let info = vk::DescriptorImageInfo::builder()
-            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-            .image_view(data.texture_image_view)
-            .sampler(data.texture_sampler);
-
-        let image_info = &[info];
-        let sampler_write = vk::WriteDescriptorSet::builder()
-            .dst_set(data.descriptor_sets[i])
-            .dst_binding(1)
-            .dst_array_element(0)
-            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .image_info(image_info);
-
-        device.update_descriptor_sets(
-            &[ubo_write, sampler_write], 
-            &[] as &[vk::CopyDescriptorSet]
-        );
+        writes.push(shadow_map_write);
     }
+    if bindings.skybox != written.skybox
+    {
+        writes.push(skybox_write);
+    }
+
+    device.update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
+    data.frames[frame].bindings = bindings;
+
     Ok(())
-}
\ No newline at end of file
+}
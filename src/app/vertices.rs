@@ -10,6 +10,7 @@ use super::appdata::AppData;
 use anyhow::{Result, anyhow};
 
 use super::buffer::*;
+use super::debug_utils::set_object_name;
 
 
 #[repr(C)]
@@ -115,6 +116,9 @@ pub unsafe fn create_vertex_buffer(
     data.vertex_buffer = vertex_buffer;
     data.vertex_buffer_memory = vertex_buffer_memory;
 
+    set_object_name(instance, device, staging_buffer, "staging:vertex")?;
+    set_object_name(instance, device, vertex_buffer, "vertex:viking_room")?;
+
     // Copy (Vertex)
     
     copy_buffer(device, data, staging_buffer, vertex_buffer, size)?;
@@ -173,6 +177,9 @@ pub unsafe fn create_index_buffer(
     data.index_buffer = index_buffer;
     data.index_buffer_memory = index_buffer_memory;
 
+    set_object_name(instance, device, staging_buffer, "staging:index")?;
+    set_object_name(instance, device, index_buffer, "index:viking_room")?;
+
     // Copy (Index)
     
     copy_buffer(device, data, staging_buffer, index_buffer, size)?;
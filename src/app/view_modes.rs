@@ -0,0 +1,99 @@
+use super::appdata::AppData;
+
+/// What the main pass shows, cycled with F3. Each mode other than `Shaded`
+/// draws with its own pipeline variant.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ViewMode
+{
+    #[default]
+    Shaded,
+    Wireframe,
+    // Shaded, then the edges on top with a second pipeline.
+    WireframeOverShaded,
+    Normals,
+    UvChecker,
+    MipLevel,
+    Depth,
+    // Additive and without depth testing, so brighter means more fragments.
+    Overdraw,
+}
+
+impl ViewMode
+{
+    pub const ALL: [ViewMode; 8] = [
+        ViewMode::Shaded,
+        ViewMode::Wireframe,
+        ViewMode::WireframeOverShaded,
+        ViewMode::Normals,
+        ViewMode::UvChecker,
+        ViewMode::MipLevel,
+        ViewMode::Depth,
+        ViewMode::Overdraw,
+    ];
+
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::Shaded => "shaded",
+            Self::Wireframe => "wireframe",
+            Self::WireframeOverShaded => "wireframe_over_shaded",
+            Self::Normals => "normals",
+            Self::UvChecker => "uv_checker",
+            Self::MipLevel => "mip_level",
+            Self::Depth => "depth",
+            Self::Overdraw => "overdraw",
+        }
+    }
+
+    /// The `VIEW_MODE` specialization constant in `shader.frag`.
+    pub fn shader_mode(self) -> u32
+    {
+        match self
+        {
+            Self::Shaded => 0,
+            Self::Wireframe | Self::WireframeOverShaded => 1,
+            Self::Normals => 2,
+            Self::UvChecker => 3,
+            Self::MipLevel => 4,
+            Self::Depth => 5,
+            Self::Overdraw => 6,
+        }
+    }
+
+    pub fn is_wireframe(self) -> bool
+    {
+        matches!(self, Self::Wireframe | Self::WireframeOverShaded)
+    }
+
+    pub fn is_supported(self, data: &AppData) -> bool
+    {
+        !self.is_wireframe() || data.fill_mode_non_solid
+    }
+
+    /// Whether the regular pipelines and the sky are drawn underneath.
+    pub fn draws_shaded(self) -> bool
+    {
+        matches!(self, Self::Shaded | Self::WireframeOverShaded)
+    }
+
+    /// The next mode the device supports.
+    pub fn next(self, data: &AppData) -> ViewMode
+    {
+        let start = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+
+        (1..=Self::ALL.len())
+            .map(|i| Self::ALL[(start + i) % Self::ALL.len()])
+            .find(|m| m.is_supported(data))
+            .unwrap_or(Self::Shaded)
+    }
+}
+
+/// The variant pipeline of a view mode, if it has one.
+pub fn view_mode_pipeline(data: &AppData, mode: ViewMode) -> Option<vulkanalia::vk::Pipeline>
+{
+    data.view_mode_pipelines
+        .iter()
+        .find(|(m, _)| *m == mode)
+        .map(|(_, p)| *p)
+}
@@ -0,0 +1,443 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+
+use vulkanalia::prelude::v1_0::*;
+
+/// Settings file read from the working directory, like the assets.
+const CONFIG_PATH: &str = "config.cfg";
+/// Prefix of environment variables that override settings from the file.
+const ENV_PREFIX: &str = "RV_";
+
+/// Runtime settings for the app.
+///
+/// Read from `config.cfg` (`key = value` lines, `#` starts a comment) and then
+/// overridden by `RV_<KEY>` environment variables, e.g. `RV_VALIDATION=false`.
+#[derive(Clone, Debug)]
+pub struct Config
+{
+    // Validation
+    pub validation: bool,
+    pub validation_synchronization: bool,
+    pub validation_best_practices: bool,
+    pub validation_gpu_assisted: bool,
+    pub debug_printf: bool,
+    pub validation_capture: bool,
+    pub validation_strict: bool,
+    pub exit_after_frames: Option<u64>,
+    // Record with `vkCmdBeginRendering` instead of render passes when the device supports it
+    pub dynamic_rendering: bool,
+    // Index textures from one update-after-bind array instead of binding them per frame
+    pub bindless: bool,
+    // Shadows
+    pub shadow_map_size: u32,
+    pub shadow_depth_bias_constant: f32,
+    pub shadow_depth_bias_slope: f32,
+    // Skybox: a directory with six faces, an equirectangular image or none
+    pub skybox: Option<String>,
+    // Level of detail: screen height fractions below which LOD 1, 2, ... are used
+    pub lod_screen_sizes: Vec<f32>,
+    pub lod_hysteresis: f32,
+    // Grid cells across a mesh for the first generated LOD
+    pub lod_grid_resolution: u32,
+    // Animation clock: seconds per frame (none for real time), speed, and a fixed time for tests
+    pub time_step: Option<f64>,
+    pub time_scale: f64,
+    pub time_override: Option<f64>,
+    pub start_paused: bool,
+    // Recording: a directory for PNGs, or a file (`-` for stdout) for raw RGBA frames
+    pub record_output: Option<String>,
+    pub record_format: RecordFormat,
+    // Simulated frames per second of the recording
+    pub record_fps: u32,
+    // Window: the mode at startup and the one Alt+Enter toggles to
+    pub window_mode: WindowMode,
+    pub fullscreen_mode: WindowMode,
+    // Monitor index for fullscreen; none uses the one the window is on
+    pub monitor: Option<usize>,
+    // Exclusive fullscreen resolution; none picks the largest
+    pub video_mode: Option<VideoModeRequest>,
+    // Frame rate limits; none renders as fast as the present mode allows
+    pub target_fps: Option<u32>,
+    pub background_fps: Option<u32>,
+    // Present mode, cycled with F2; FIFO is used when the surface doesn't support it
+    pub present_mode: vk::PresentModeKHR,
+    // Debug overlay, toggled with F1
+    pub hud: bool,
+    pub hud_scale: f32,
+    // Post-processing, applied in order after the scene is resolved
+    pub post_chain: Vec<PostEffect>,
+    pub exposure: f32,
+    pub tonemap_operator: TonemapOperator,
+    pub gamma: f32,
+    // Debug messenger filter
+    pub debug_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub debug_mute: Vec<String>,
+}
+
+impl Default for Config
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            validation: true,
+            validation_synchronization: false,
+            validation_best_practices: false,
+            validation_gpu_assisted: false,
+            debug_printf: false,
+            validation_capture: false,
+            validation_strict: false,
+            exit_after_frames: None,
+            dynamic_rendering: true,
+            bindless: true,
+            shadow_map_size: 2048,
+            shadow_depth_bias_constant: 1.25,
+            shadow_depth_bias_slope: 1.75,
+            skybox: None,
+            lod_screen_sizes: vec![0.4, 0.2, 0.1],
+            lod_hysteresis: 0.15,
+            lod_grid_resolution: 64,
+            time_step: None,
+            time_scale: 1.0,
+            time_override: None,
+            start_paused: false,
+            record_output: None,
+            record_format: RecordFormat::Png,
+            record_fps: 60,
+            window_mode: WindowMode::Windowed,
+            fullscreen_mode: WindowMode::Borderless,
+            monitor: None,
+            video_mode: None,
+            target_fps: None,
+            background_fps: Some(15),
+            present_mode: vk::PresentModeKHR::MAILBOX,
+            hud: true,
+            hud_scale: 2.0,
+            post_chain: vec![PostEffect::Tonemap, PostEffect::Gamma],
+            exposure: 1.0,
+            tonemap_operator: TonemapOperator::Aces,
+            gamma: 2.2,
+            debug_severity: vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            debug_mute: Vec::new(),
+        }
+    }
+}
+
+impl Config
+{
+    /// Loads the settings file (if any) and applies the environment overrides.
+    pub fn load() -> Result<Self>
+    {
+        let mut config = Self::default();
+
+        if let Ok(contents) = fs::read_to_string(CONFIG_PATH)
+        {
+            for (number, line) in contents.lines().enumerate()
+            {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty()
+                {
+                    continue;
+                }
+
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("{}:{}: expected `key = value`.", CONFIG_PATH, number + 1))?;
+
+                config.set(key.trim(), value.trim())
+                    .map_err(|e| anyhow!("{}:{}: {}", CONFIG_PATH, number + 1, e))?;
+            }
+        }
+
+        for (key, value) in std::env::vars()
+        {
+            if let Some(key) = key.strip_prefix(ENV_PREFIX)
+            {
+                config.set(&key.to_lowercase(), value.trim())
+                    .map_err(|e| anyhow!("{}{}: {}", ENV_PREFIX, key, e))?;
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()>
+    {
+        match key
+        {
+            "validation" => self.validation = parse_bool(value)?,
+            "validation_synchronization" => self.validation_synchronization = parse_bool(value)?,
+            "validation_best_practices" => self.validation_best_practices = parse_bool(value)?,
+            "validation_gpu_assisted" => self.validation_gpu_assisted = parse_bool(value)?,
+            "debug_printf" => self.debug_printf = parse_bool(value)?,
+            "validation_capture" => self.validation_capture = parse_bool(value)?,
+            "validation_strict" => self.validation_strict = parse_bool(value)?,
+            "exit_after_frames" => self.exit_after_frames = parse_optional(value)?,
+            "dynamic_rendering" => self.dynamic_rendering = parse_bool(value)?,
+            "bindless" => self.bindless = parse_bool(value)?,
+            "shadow_map_size" => self.shadow_map_size = parse_number(value)?,
+            "shadow_depth_bias_constant" => self.shadow_depth_bias_constant = parse_number(value)?,
+            "shadow_depth_bias_slope" => self.shadow_depth_bias_slope = parse_number(value)?,
+            "skybox" => self.skybox = (!value.is_empty() && value != "none").then(|| value.to_string()),
+            "lod_screen_sizes" => self.lod_screen_sizes = parse_list(value)
+                .iter()
+                .map(|v| parse_number(v))
+                .collect::<Result<Vec<_>>>()?,
+            "lod_hysteresis" => self.lod_hysteresis = parse_number(value)?,
+            "lod_grid_resolution" => self.lod_grid_resolution = parse_number(value)?,
+            "time_step" => self.time_step = parse_optional(value)?,
+            "time_scale" => self.time_scale = parse_number(value)?,
+            "time_override" => self.time_override = parse_optional(value)?,
+            "start_paused" => self.start_paused = parse_bool(value)?,
+            "record_output" => self.record_output = (!value.is_empty() && value != "none").then(|| value.to_string()),
+            "record_format" => self.record_format = RecordFormat::parse(&value.to_lowercase())?,
+            "record_fps" => self.record_fps = parse_number(value)?,
+            "window_mode" => self.window_mode = WindowMode::parse(&value.to_lowercase())?,
+            "fullscreen_mode" => self.fullscreen_mode = WindowMode::parse(&value.to_lowercase())?,
+            "monitor" => self.monitor = parse_optional(value)?,
+            "video_mode" => self.video_mode = match value
+            {
+                "" | "none" => None,
+                _ => Some(VideoModeRequest::parse(value)?),
+            },
+            "target_fps" => self.target_fps = parse_optional(value)?,
+            "background_fps" => self.background_fps = parse_optional(value)?,
+            "present_mode" => self.present_mode = parse_present_mode(value)?,
+            "hud" => self.hud = parse_bool(value)?,
+            "hud_scale" => self.hud_scale = parse_number(value)?,
+            "post_chain" => self.post_chain = parse_list(value)
+                .iter()
+                .map(|e| PostEffect::parse(e))
+                .collect::<Result<Vec<_>>>()?,
+            "exposure" => self.exposure = parse_number(value)?,
+            "tonemap_operator" => self.tonemap_operator = TonemapOperator::parse(&value.to_lowercase())?,
+            "gamma" => self.gamma = parse_number(value)?,
+            "debug_severity" => self.debug_severity = parse_severity(value)?,
+            "debug_mute" => self.debug_mute = parse_list(value),
+            // Unknown keys are not fatal so older builds can read newer config files.
+            _ => warn!("Unknown config key `{}`.", key),
+        }
+
+        Ok(())
+    }
+}
+
+/// How recorded frames are written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecordFormat
+{
+    // `frame_000000.png`, ... in the output directory.
+    Png,
+    // Tightly packed RGBA8 frames back to back, for piping into an encoder.
+    Raw,
+}
+
+impl RecordFormat
+{
+    pub fn parse(name: &str) -> Result<Self>
+    {
+        match name
+        {
+            "png" => Ok(Self::Png),
+            "raw" => Ok(Self::Raw),
+            _ => Err(anyhow!("expected png or raw, got `{}`.", name)),
+        }
+    }
+}
+
+/// A fullscreen pass in the post-process chain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PostEffect
+{
+    /// Exposure followed by the configured tonemapping operator.
+    Tonemap,
+    /// Gamma encoding for the display.
+    Gamma,
+}
+
+impl PostEffect
+{
+    pub fn parse(name: &str) -> Result<Self>
+    {
+        match name
+        {
+            "tonemap" => Ok(Self::Tonemap),
+            "gamma" => Ok(Self::Gamma),
+            _ => Err(anyhow!("unknown post effect `{}`.", name)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TonemapOperator
+{
+    Reinhard,
+    Aces,
+}
+
+impl TonemapOperator
+{
+    pub fn parse(name: &str) -> Result<Self>
+    {
+        match name
+        {
+            "reinhard" => Ok(Self::Reinhard),
+            "aces" => Ok(Self::Aces),
+            _ => Err(anyhow!("expected reinhard or aces, got `{}`.", name)),
+        }
+    }
+}
+
+/// How the window covers the screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WindowMode
+{
+    Windowed,
+    // A fullscreen window at the desktop resolution.
+    Borderless,
+    // Takes the monitor over and switches it to `video_mode`.
+    Exclusive,
+}
+
+impl WindowMode
+{
+    pub fn parse(name: &str) -> Result<Self>
+    {
+        match name
+        {
+            "windowed" => Ok(Self::Windowed),
+            "borderless" => Ok(Self::Borderless),
+            "exclusive" => Ok(Self::Exclusive),
+            _ => Err(anyhow!("expected windowed, borderless or exclusive, got `{}`.", name)),
+        }
+    }
+}
+
+/// A requested video mode, `<width>x<height>` with an optional `@<hz>`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VideoModeRequest
+{
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: Option<u32>,
+}
+
+impl VideoModeRequest
+{
+    pub fn parse(value: &str) -> Result<Self>
+    {
+        let error = || anyhow!("expected a video mode like 1920x1080 or 1920x1080@60, got `{}`.", value);
+
+        let (size, refresh_rate) = match value.split_once('@')
+        {
+            Some((size, hz)) => (size, Some(hz.trim().parse().map_err(|_| error())?)),
+            None => (value, None),
+        };
+        let (width, height) = size.split_once('x').ok_or_else(error)?;
+
+        Ok(Self
+        {
+            width: width.trim().parse().map_err(|_| error())?,
+            height: height.trim().parse().map_err(|_| error())?,
+            refresh_rate,
+        })
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool>
+{
+    match value.to_lowercase().as_str()
+    {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(anyhow!("expected a boolean, got `{}`.", value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T>
+{
+    value.parse::<T>().map_err(|_| anyhow!("expected a number, got `{}`.", value))
+}
+
+fn parse_optional<T: std::str::FromStr>(value: &str) -> Result<Option<T>>
+{
+    if value.is_empty() || value == "none"
+    {
+        return Ok(None);
+    }
+
+    parse_number(value).map(Some)
+}
+
+fn parse_severity(value: &str) -> Result<vk::DebugUtilsMessageSeverityFlagsEXT>
+{
+    match value.to_lowercase().as_str()
+    {
+        "verbose" => Ok(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE),
+        "info" => Ok(vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+        "warning" => Ok(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING),
+        "error" => Ok(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR),
+        _ => Err(anyhow!("expected verbose, info, warning or error, got `{}`.", value)),
+    }
+}
+
+fn parse_present_mode(value: &str) -> Result<vk::PresentModeKHR>
+{
+    match value.to_lowercase().as_str()
+    {
+        "fifo" => Ok(vk::PresentModeKHR::FIFO),
+        "fifo_relaxed" => Ok(vk::PresentModeKHR::FIFO_RELAXED),
+        "mailbox" => Ok(vk::PresentModeKHR::MAILBOX),
+        "immediate" => Ok(vk::PresentModeKHR::IMMEDIATE),
+        _ => Err(anyhow!("expected fifo, fifo_relaxed, mailbox or immediate, got `{}`.", value)),
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String>
+{
+    value
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn video_mode_with_and_without_refresh_rate()
+    {
+        assert_eq!(
+            VideoModeRequest::parse("1920x1080").unwrap(),
+            VideoModeRequest { width: 1920, height: 1080, refresh_rate: None },
+        );
+        assert_eq!(
+            VideoModeRequest::parse("2560x1440@144").unwrap(),
+            VideoModeRequest { width: 2560, height: 1440, refresh_rate: Some(144) },
+        );
+        assert_eq!(
+            VideoModeRequest::parse("1280 x 720 @ 60").unwrap(),
+            VideoModeRequest { width: 1280, height: 720, refresh_rate: Some(60) },
+        );
+    }
+
+    #[test]
+    fn malformed_video_modes_are_rejected()
+    {
+        for value in ["", "1920", "1920x", "x1080", "1920x1080@", "1920x1080@fast", "-1x1080", "1920*1080"]
+        {
+            assert!(VideoModeRequest::parse(value).is_err(), "`{}` should not parse", value);
+        }
+    }
+
+    #[test]
+    fn window_modes()
+    {
+        assert_eq!(WindowMode::parse("borderless").unwrap(), WindowMode::Borderless);
+        assert!(WindowMode::parse("fullscreen").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::{Fullscreen, Window};
+
+use crate::config::{VideoModeRequest, WindowMode};
+
+/// Switches the window between windowed and fullscreen and remembers where
+/// the window was, so leaving fullscreen puts it back.
+#[derive(Clone, Debug)]
+pub struct Display
+{
+    pub mode: WindowMode,
+    // The mode Alt+Enter switches to from windowed.
+    fullscreen_mode: WindowMode,
+    monitor: Option<usize>,
+    video_mode: Option<VideoModeRequest>,
+    windowed_size: PhysicalSize<u32>,
+    windowed_position: Option<PhysicalPosition<i32>>,
+}
+
+impl Display
+{
+    pub fn new(
+        window: &Window,
+        fullscreen_mode: WindowMode,
+        monitor: Option<usize>,
+        video_mode: Option<VideoModeRequest>,
+    ) -> Self
+    {
+        Self
+        {
+            mode: WindowMode::Windowed,
+            fullscreen_mode: if fullscreen_mode == WindowMode::Windowed { WindowMode::Borderless } else { fullscreen_mode },
+            monitor,
+            video_mode,
+            windowed_size: window.inner_size(),
+            windowed_position: window.outer_position().ok(),
+        }
+    }
+
+    /// Alt+Enter: fullscreen from windowed, windowed from either fullscreen mode.
+    pub fn toggle(&mut self, window: &Window)
+    {
+        let mode = if self.mode == WindowMode::Windowed { self.fullscreen_mode } else { WindowMode::Windowed };
+        self.set_mode(window, mode);
+    }
+
+    pub fn set_mode(&mut self, window: &Window, mode: WindowMode)
+    {
+        if mode == self.mode
+        {
+            return;
+        }
+
+        if self.mode == WindowMode::Windowed
+        {
+            self.windowed_size = window.inner_size();
+            self.windowed_position = window.outer_position().ok();
+        }
+
+        match mode
+        {
+            WindowMode::Windowed =>
+            {
+                window.set_fullscreen(None);
+                window.set_inner_size(self.windowed_size);
+                if let Some(position) = self.windowed_position
+                {
+                    window.set_outer_position(position);
+                }
+            },
+            WindowMode::Borderless =>
+            {
+                window.set_fullscreen(Some(Fullscreen::Borderless(self.pick_monitor(window))));
+            },
+            WindowMode::Exclusive =>
+            {
+                match self.pick_monitor(window).and_then(|m| self.pick_video_mode(&m))
+                {
+                    Some(video_mode) =>
+                    {
+                        info!("Exclusive fullscreen at {:?}.", video_mode);
+                        window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+                    },
+                    None =>
+                    {
+                        warn!("No video mode for exclusive fullscreen, using borderless.");
+                        window.set_fullscreen(Some(Fullscreen::Borderless(self.pick_monitor(window))));
+                    },
+                }
+            },
+        }
+
+        self.mode = mode;
+    }
+
+    /// The configured monitor, or the one the window is on.
+    fn pick_monitor(&self, window: &Window) -> Option<MonitorHandle>
+    {
+        match self.monitor
+        {
+            Some(index) => window.available_monitors().nth(index).or_else(||
+            {
+                warn!("Monitor {} not found, using the current one.", index);
+                window.current_monitor()
+            }),
+            None => window.current_monitor(),
+        }
+    }
+
+    /// The requested mode if the monitor has it, otherwise its largest and fastest one.
+    fn pick_video_mode(&self, monitor: &MonitorHandle) -> Option<VideoMode>
+    {
+        let requested = self.video_mode.and_then(|r| monitor
+            .video_modes()
+            .filter(|m| m.size() == PhysicalSize::new(r.width, r.height))
+            .filter(|m| r.refresh_rate.is_none_or(|hz| (m.refresh_rate_millihertz() + 500) / 1000 == hz))
+            .max_by_key(|m| (m.refresh_rate_millihertz(), m.bit_depth())));
+
+        if requested.is_none() && self.video_mode.is_some()
+        {
+            warn!("Video mode {:?} is not supported by the monitor.", self.video_mode);
+        }
+
+        requested.or_else(|| monitor
+            .video_modes()
+            .max_by_key(|m| (m.size().width * m.size().height, m.refresh_rate_millihertz(), m.bit_depth())))
+    }
+}
@@ -0,0 +1,660 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use anyhow::{anyhow, Result};
+
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+/// Bindings file read from the working directory, next to `config.cfg`.
+const BINDINGS_PATH: &str = "bindings.cfg";
+/// Pixels of touchpad scrolling that count as one wheel line.
+const PIXELS_PER_LINE: f64 = 40.0;
+
+/// Something the user can trigger, independent of the key or button bound to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action
+{
+    RemoveModel,
+    AddModel,
+    ToggleHud,
+    CyclePresentMode,
+    ToggleFullscreen,
+    CycleViewMode,
+    Select,
+    Orbit,
+    TogglePause,
+    StepFrame,
+    SlowDown,
+    SpeedUp,
+}
+
+impl Action
+{
+    pub const ALL: [Action; 12] = [
+        Action::RemoveModel,
+        Action::AddModel,
+        Action::ToggleHud,
+        Action::CyclePresentMode,
+        Action::ToggleFullscreen,
+        Action::CycleViewMode,
+        Action::Select,
+        Action::Orbit,
+        Action::TogglePause,
+        Action::StepFrame,
+        Action::SlowDown,
+        Action::SpeedUp,
+    ];
+
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::RemoveModel => "remove_model",
+            Self::AddModel => "add_model",
+            Self::ToggleHud => "toggle_hud",
+            Self::CyclePresentMode => "cycle_present_mode",
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::CycleViewMode => "cycle_view_mode",
+            Self::Select => "select",
+            Self::Orbit => "orbit",
+            Self::TogglePause => "toggle_pause",
+            Self::StepFrame => "step_frame",
+            Self::SlowDown => "slow_down",
+            Self::SpeedUp => "speed_up",
+        }
+    }
+}
+
+/// A value that changes continuously, summed over the frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Axis
+{
+    LookX,
+    LookY,
+    Zoom,
+}
+
+impl Axis
+{
+    pub const ALL: [Axis; 3] = [Axis::LookX, Axis::LookY, Axis::Zoom];
+
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            Self::LookX => "look_x",
+            Self::LookY => "look_y",
+            Self::Zoom => "zoom",
+        }
+    }
+}
+
+/// A key or mouse button.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Button
+{
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+/// A button together with the modifiers that have to be held with it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ButtonBinding
+{
+    pub button: Button,
+    pub modifiers: ModifiersState,
+}
+
+/// Where an axis reads its value from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AxisSource
+{
+    // Cursor movement in pixels.
+    MouseX,
+    MouseY,
+    // Wheel lines.
+    Scroll,
+    // -1 while the first button is held, +1 for the second, per frame.
+    Buttons(Button, Button),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AxisBinding
+{
+    pub source: AxisSource,
+    pub scale: f32,
+}
+
+/// Maps actions and axes to inputs.
+///
+/// Read from `bindings.cfg` (`name = binding, binding` lines, `#` starts a
+/// comment); actions missing from the file keep their default bindings.
+#[derive(Clone, Debug)]
+pub struct Bindings
+{
+    pub actions: HashMap<Action, Vec<ButtonBinding>>,
+    pub axes: HashMap<Axis, Vec<AxisBinding>>,
+}
+
+impl Default for Bindings
+{
+    fn default() -> Self
+    {
+        let key = |key| vec![ButtonBinding { button: Button::Key(key), modifiers: ModifiersState::empty() }];
+        let axis = |source| vec![AxisBinding { source, scale: 1.0 }];
+
+        Self
+        {
+            actions: HashMap::from([
+                (Action::RemoveModel, key(VirtualKeyCode::Left)),
+                (Action::AddModel, key(VirtualKeyCode::Right)),
+                (Action::ToggleHud, key(VirtualKeyCode::F1)),
+                (Action::CyclePresentMode, key(VirtualKeyCode::F2)),
+                (Action::ToggleFullscreen, vec![ButtonBinding { button: Button::Key(VirtualKeyCode::Return), modifiers: ModifiersState::ALT }]),
+                (Action::CycleViewMode, key(VirtualKeyCode::F3)),
+                (Action::Select, vec![ButtonBinding { button: Button::Mouse(MouseButton::Left), modifiers: ModifiersState::empty() }]),
+                (Action::Orbit, vec![ButtonBinding { button: Button::Mouse(MouseButton::Right), modifiers: ModifiersState::empty() }]),
+                (Action::TogglePause, key(VirtualKeyCode::Space)),
+                (Action::StepFrame, key(VirtualKeyCode::Period)),
+                (Action::SlowDown, key(VirtualKeyCode::LBracket)),
+                (Action::SpeedUp, key(VirtualKeyCode::RBracket)),
+            ]),
+            axes: HashMap::from([
+                (Axis::LookX, axis(AxisSource::MouseX)),
+                (Axis::LookY, axis(AxisSource::MouseY)),
+                (Axis::Zoom, axis(AxisSource::Scroll)),
+            ]),
+        }
+    }
+}
+
+impl Bindings
+{
+    /// Loads the bindings file, if there is one, over the defaults.
+    pub fn load() -> Result<Self>
+    {
+        let mut bindings = Self::default();
+
+        if let Ok(contents) = fs::read_to_string(BINDINGS_PATH)
+        {
+            for (number, line) in contents.lines().enumerate()
+            {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty()
+                {
+                    continue;
+                }
+
+                let (name, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("{}:{}: expected `name = binding`.", BINDINGS_PATH, number + 1))?;
+
+                bindings.set(name.trim(), value.trim())
+                    .map_err(|e| anyhow!("{}:{}: {}", BINDINGS_PATH, number + 1, e))?;
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()>
+    {
+        let values = value.split(',').map(|v| v.trim()).filter(|v| !v.is_empty());
+
+        if let Some(action) = Action::ALL.iter().find(|a| a.name() == name)
+        {
+            let bindings = values.map(parse_button_binding).collect::<Result<Vec<_>>>()?;
+            self.actions.insert(*action, bindings);
+        }
+        else if let Some(axis) = Axis::ALL.iter().find(|a| a.name() == name)
+        {
+            let bindings = values.map(parse_axis_binding).collect::<Result<Vec<_>>>()?;
+            self.axes.insert(*axis, bindings);
+        }
+        else
+        {
+            warn!("Unknown binding `{}`.", name);
+        }
+
+        Ok(())
+    }
+}
+
+/// Input state for the current frame, fed with window events.
+#[derive(Clone, Debug)]
+pub struct Input
+{
+    bindings: Bindings,
+    modifiers: ModifiersState,
+    held: HashSet<Button>,
+    // Actions latched by the button that triggered them, until that button is released.
+    active: HashMap<Action, Button>,
+    pressed: HashSet<Action>,
+    released: HashSet<Action>,
+    pub cursor: Option<PhysicalPosition<f64>>,
+    cursor_delta: (f64, f64),
+    scroll: f64,
+}
+
+impl Input
+{
+    pub fn new(bindings: Bindings) -> Self
+    {
+        Self
+        {
+            bindings,
+            modifiers: ModifiersState::empty(),
+            held: HashSet::new(),
+            active: HashMap::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            cursor: None,
+            cursor_delta: (0.0, 0.0),
+            scroll: 0.0,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent)
+    {
+        match event
+        {
+            WindowEvent::KeyboardInput { input, .. } =>
+            {
+                if let Some(key) = input.virtual_keycode
+                {
+                    self.set_button(Button::Key(key), input.state);
+                }
+            },
+            WindowEvent::MouseInput { button, state, .. } => self.set_button(Button::Mouse(*button), *state),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+            WindowEvent::CursorMoved { position, .. } =>
+            {
+                if let Some(last) = self.cursor
+                {
+                    self.cursor_delta.0 += position.x - last.x;
+                    self.cursor_delta.1 += position.y - last.y;
+                }
+                self.cursor = Some(*position);
+            },
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } => self.scroll += match delta
+            {
+                MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                MouseScrollDelta::PixelDelta(p) => p.y / PIXELS_PER_LINE,
+            },
+            // Nothing is held once the window has lost the keyboard.
+            WindowEvent::Focused(false) =>
+            {
+                self.held.clear();
+                self.released.extend(self.active.drain().map(|(action, _)| action));
+                self.modifiers = ModifiersState::empty();
+            },
+            _ => { }
+        }
+    }
+
+    fn set_button(&mut self, button: Button, state: ElementState)
+    {
+        match state
+        {
+            // Key repeats arrive as presses of a button that is already held.
+            ElementState::Pressed if self.held.insert(button) =>
+            {
+                // Modifiers match exactly so Alt+Enter doesn't also trigger a plain Enter binding.
+                for (action, bindings) in &self.bindings.actions
+                {
+                    if bindings.iter().any(|b| b.button == button && b.modifiers == self.modifiers)
+                    {
+                        self.active.entry(*action).or_insert(button);
+                        self.pressed.insert(*action);
+                    }
+                }
+            },
+            // Releases ignore the modifiers, so changing them mid-drag can't leave an action stuck.
+            ElementState::Released if self.held.remove(&button) =>
+            {
+                let released = self.active.iter().filter(|(_, b)| **b == button).map(|(a, _)| *a).collect::<Vec<_>>();
+                for action in released
+                {
+                    self.active.remove(&action);
+                    self.released.insert(action);
+                }
+            },
+            _ => { }
+        }
+    }
+
+    /// Clears the per-frame state; call after every `MainEventsCleared`, rendered or not.
+    pub fn end_frame(&mut self)
+    {
+        self.pressed.clear();
+        self.released.clear();
+        self.cursor_delta = (0.0, 0.0);
+        self.scroll = 0.0;
+    }
+
+    /// The action was triggered this frame.
+    pub fn pressed(&self, action: Action) -> bool
+    {
+        self.pressed.contains(&action)
+    }
+
+    /// Held since a press with the bound modifiers, whatever the modifiers are now.
+    pub fn held(&self, action: Action) -> bool
+    {
+        self.active.contains_key(&action)
+    }
+
+    /// The button that triggered the action was released this frame.
+    pub fn released(&self, action: Action) -> bool
+    {
+        self.released.contains(&action)
+    }
+
+    /// The axis value accumulated over this frame.
+    pub fn axis(&self, axis: Axis) -> f32
+    {
+        let Some(bindings) = self.bindings.axes.get(&axis) else { return 0.0 };
+
+        bindings
+            .iter()
+            .map(|b| b.scale * match b.source
+            {
+                AxisSource::MouseX => self.cursor_delta.0 as f32,
+                AxisSource::MouseY => self.cursor_delta.1 as f32,
+                AxisSource::Scroll => self.scroll as f32,
+                AxisSource::Buttons(negative, positive) =>
+                {
+                    self.held.contains(&positive) as i32 as f32 - self.held.contains(&negative) as i32 as f32
+                },
+            })
+            .sum()
+    }
+}
+
+/// `[modifier+]*button`, e.g. `f1`, `alt+return` or `mouse_left`.
+fn parse_button_binding(value: &str) -> Result<ButtonBinding>
+{
+    let mut parts = value.split('+').map(|p| p.trim().to_lowercase()).collect::<Vec<_>>();
+    let button = parse_button(&parts.pop().unwrap_or_default())?;
+
+    let mut modifiers = ModifiersState::empty();
+    for part in parts
+    {
+        modifiers |= match part.as_str()
+        {
+            "shift" => ModifiersState::SHIFT,
+            "ctrl" => ModifiersState::CTRL,
+            "alt" => ModifiersState::ALT,
+            "logo" => ModifiersState::LOGO,
+            _ => return Err(anyhow!("expected shift, ctrl, alt or logo, got `{}`.", part)),
+        };
+    }
+
+    Ok(ButtonBinding { button, modifiers })
+}
+
+/// `mouse_x`, `mouse_y`, `scroll` or `<negative>/<positive>` buttons, with an optional `* <scale>`.
+fn parse_axis_binding(value: &str) -> Result<AxisBinding>
+{
+    let (source, scale) = match value.split_once('*')
+    {
+        Some((source, scale)) => (
+            source.trim(),
+            scale.trim().parse().map_err(|_| anyhow!("expected a scale, got `{}`.", scale.trim()))?,
+        ),
+        None => (value, 1.0),
+    };
+
+    let source = match source.to_lowercase().as_str()
+    {
+        "mouse_x" => AxisSource::MouseX,
+        "mouse_y" => AxisSource::MouseY,
+        "scroll" => AxisSource::Scroll,
+        buttons => match buttons.split_once('/')
+        {
+            Some((negative, positive)) => AxisSource::Buttons(parse_button(negative.trim())?, parse_button(positive.trim())?),
+            None => return Err(anyhow!("expected mouse_x, mouse_y, scroll or `<key>/<key>`, got `{}`.", source)),
+        },
+    };
+
+    Ok(AxisBinding { source, scale })
+}
+
+fn parse_button(name: &str) -> Result<Button>
+{
+    let button = match name
+    {
+        "mouse_left" => Button::Mouse(MouseButton::Left),
+        "mouse_right" => Button::Mouse(MouseButton::Right),
+        "mouse_middle" => Button::Mouse(MouseButton::Middle),
+        _ => Button::Key(parse_key(name).ok_or_else(|| anyhow!("unknown key or button `{}`.", name))?),
+    };
+
+    Ok(button)
+}
+
+fn parse_key(name: &str) -> Option<VirtualKeyCode>
+{
+    use VirtualKeyCode::*;
+
+    const LETTERS: [VirtualKeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [VirtualKeyCode; 10] = [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const FUNCTION: [VirtualKeyCode; 12] = [F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12];
+
+    let bytes = name.as_bytes();
+    match bytes
+    {
+        [c @ b'a'..=b'z'] => return Some(LETTERS[(c - b'a') as usize]),
+        [c @ b'0'..=b'9'] => return Some(DIGITS[(c - b'0') as usize]),
+        _ => { }
+    }
+
+    if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse::<usize>().ok())
+    {
+        return FUNCTION.get(n.wrapping_sub(1)).copied();
+    }
+
+    let key = match name
+    {
+        "left" => Left,
+        "right" => Right,
+        "up" => Up,
+        "down" => Down,
+        "space" => Space,
+        "return" | "enter" => Return,
+        "escape" => Escape,
+        "tab" => Tab,
+        "backspace" => Back,
+        "delete" => Delete,
+        "insert" => Insert,
+        "home" => Home,
+        "end" => End,
+        "page_up" => PageUp,
+        "page_down" => PageDown,
+        "minus" => Minus,
+        "equals" => Equals,
+        "comma" => Comma,
+        "period" => Period,
+        "bracket_left" => LBracket,
+        "bracket_right" => RBracket,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn button(button: Button, modifiers: ModifiersState) -> ButtonBinding
+    {
+        ButtonBinding { button, modifiers }
+    }
+
+    #[test]
+    fn key_names()
+    {
+        assert_eq!(parse_key("a"), Some(VirtualKeyCode::A));
+        assert_eq!(parse_key("z"), Some(VirtualKeyCode::Z));
+        assert_eq!(parse_key("0"), Some(VirtualKeyCode::Key0));
+        assert_eq!(parse_key("f1"), Some(VirtualKeyCode::F1));
+        assert_eq!(parse_key("f12"), Some(VirtualKeyCode::F12));
+        assert_eq!(parse_key("enter"), Some(VirtualKeyCode::Return));
+        assert_eq!(parse_key("bracket_left"), Some(VirtualKeyCode::LBracket));
+    }
+
+    #[test]
+    fn unknown_key_names()
+    {
+        for name in ["", "f0", "f13", "ab", "A", "shift", "mouse_left"]
+        {
+            assert_eq!(parse_key(name), None, "`{}` should not be a key", name);
+        }
+    }
+
+    #[test]
+    fn button_bindings_with_modifiers()
+    {
+        assert_eq!(parse_button_binding("f1").unwrap(), button(Button::Key(VirtualKeyCode::F1), ModifiersState::empty()));
+        assert_eq!(
+            parse_button_binding("Ctrl + Shift + S").unwrap(),
+            button(Button::Key(VirtualKeyCode::S), ModifiersState::CTRL | ModifiersState::SHIFT),
+        );
+        assert_eq!(
+            parse_button_binding("alt+mouse_right").unwrap(),
+            button(Button::Mouse(MouseButton::Right), ModifiersState::ALT),
+        );
+    }
+
+    #[test]
+    fn malformed_button_bindings()
+    {
+        for value in ["", "hyper+a", "alt+", "alt+nope", "a+b"]
+        {
+            assert!(parse_button_binding(value).is_err(), "`{}` should not parse", value);
+        }
+    }
+
+    #[test]
+    fn axis_bindings()
+    {
+        assert_eq!(parse_axis_binding("mouse_x").unwrap(), AxisBinding { source: AxisSource::MouseX, scale: 1.0 });
+        assert_eq!(parse_axis_binding("scroll * -0.5").unwrap(), AxisBinding { source: AxisSource::Scroll, scale: -0.5 });
+        assert_eq!(
+            parse_axis_binding("minus/equals").unwrap(),
+            AxisBinding
+            {
+                source: AxisSource::Buttons(Button::Key(VirtualKeyCode::Minus), Button::Key(VirtualKeyCode::Equals)),
+                scale: 1.0,
+            },
+        );
+    }
+
+    #[test]
+    fn malformed_axis_bindings()
+    {
+        for value in ["", "mouse_z", "scroll *", "scroll * fast", "minus/", "minus/nope"]
+        {
+            assert!(parse_axis_binding(value).is_err(), "`{}` should not parse", value);
+        }
+    }
+
+    #[test]
+    fn set_replaces_the_bindings_of_a_name()
+    {
+        let mut bindings = Bindings::default();
+
+        bindings.set("toggle_hud", "h, shift+f1").unwrap();
+        assert_eq!(bindings.actions[&Action::ToggleHud], vec![
+            button(Button::Key(VirtualKeyCode::H), ModifiersState::empty()),
+            button(Button::Key(VirtualKeyCode::F1), ModifiersState::SHIFT),
+        ]);
+
+        bindings.set("zoom", "scroll * 2").unwrap();
+        assert_eq!(bindings.axes[&Axis::Zoom], vec![AxisBinding { source: AxisSource::Scroll, scale: 2.0 }]);
+
+        // An empty value unbinds the action.
+        bindings.set("select", "").unwrap();
+        assert!(bindings.actions[&Action::Select].is_empty());
+    }
+
+    fn key_event(input: &mut Input, key: VirtualKeyCode, state: ElementState)
+    {
+        input.set_button(Button::Key(key), state);
+    }
+
+    #[test]
+    fn modifiers_only_matter_when_pressing()
+    {
+        let mut input = Input::new(Bindings::default());
+
+        // Alt+Return doesn't trigger on Return alone.
+        key_event(&mut input, VirtualKeyCode::Return, ElementState::Pressed);
+        assert!(!input.pressed(Action::ToggleFullscreen));
+        key_event(&mut input, VirtualKeyCode::Return, ElementState::Released);
+        input.end_frame();
+
+        input.handle_event(&WindowEvent::ModifiersChanged(ModifiersState::ALT));
+        key_event(&mut input, VirtualKeyCode::Return, ElementState::Pressed);
+        assert!(input.pressed(Action::ToggleFullscreen));
+        input.end_frame();
+
+        // Letting go of Alt first still ends the action with Return.
+        input.handle_event(&WindowEvent::ModifiersChanged(ModifiersState::empty()));
+        assert!(input.held(Action::ToggleFullscreen));
+        key_event(&mut input, VirtualKeyCode::Return, ElementState::Released);
+        assert!(input.released(Action::ToggleFullscreen));
+        assert!(!input.held(Action::ToggleFullscreen));
+    }
+
+    #[test]
+    fn drag_ends_when_a_modifier_changes_mid_drag()
+    {
+        let mut input = Input::new(Bindings::default());
+
+        input.set_button(Button::Mouse(MouseButton::Right), ElementState::Pressed);
+        assert!(input.pressed(Action::Orbit));
+        input.end_frame();
+
+        input.handle_event(&WindowEvent::ModifiersChanged(ModifiersState::SHIFT));
+        assert!(input.held(Action::Orbit));
+
+        input.set_button(Button::Mouse(MouseButton::Right), ElementState::Released);
+        assert!(input.released(Action::Orbit));
+        assert!(!input.held(Action::Orbit));
+
+        input.end_frame();
+        assert!(!input.released(Action::Orbit));
+    }
+
+    #[test]
+    fn key_repeats_are_not_presses()
+    {
+        let mut input = Input::new(Bindings::default());
+
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Pressed);
+        input.end_frame();
+        key_event(&mut input, VirtualKeyCode::Space, ElementState::Pressed);
+
+        assert!(!input.pressed(Action::TogglePause));
+        assert!(input.held(Action::TogglePause));
+    }
+
+    #[test]
+    fn set_rejects_bad_values_and_ignores_unknown_names()
+    {
+        let mut bindings = Bindings::default();
+
+        assert!(bindings.set("toggle_hud", "f1, nope").is_err());
+        assert_eq!(bindings.actions[&Action::ToggleHud], Bindings::default().actions[&Action::ToggleHud]);
+        assert!(bindings.set("zoom", "wheel").is_err());
+
+        assert!(bindings.set("no_such_action", "f1").is_ok());
+    }
+}
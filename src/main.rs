@@ -1,19 +1,28 @@
-#![allow(dead_code, unused_variables, clippy::too_many_arguments, clippy::unnecessary_wraps)]
+#![allow(dead_code, unused_variables, clippy::too_many_arguments, clippy::unnecessary_wraps, clippy::suspicious_else_formatting)]
 
 mod app;
+mod config;
+mod display;
+mod input;
+mod pacing;
 use app::App;
+use config::Config;
+use input::Bindings;
+use pacing::FrameLimiter;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use winit::dpi::LogicalSize;
 use winit::event::{Event, WindowEvent};
-use winit::event::{ElementState, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
+use winit::platform::run_return::EventLoopExtRunReturn;
 use winit::window::WindowBuilder;
 
 extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 
 #[rustfmt::skip]
 fn main() -> Result<()> 
@@ -21,25 +30,64 @@ fn main() -> Result<()>
     ::std::env::set_var("RUST_LOG", "info");
     pretty_env_logger::init();
 
+    let config = Config::load()?;
+    let bindings = Bindings::load()?;
+
     // Window
 
-    let event_loop = EventLoop::new();
+    let mut event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Vulkan Tutorial (Rust)")
         .with_inner_size(LogicalSize::new(1024, 768))
         .build(&event_loop)?;
 
     // App
-    let mut app = unsafe { App::create(&window)? };
+    let exit_after_frames = config.exit_after_frames;
+    let capture_validation = config.validation_capture || config.validation_strict;
+    let (target_fps, background_fps) = (config.target_fps, config.background_fps);
+    let mut app = unsafe { App::create(&window, config, bindings)? };
     let mut destroying = false;
     let mut minimized = false;
-    event_loop.run(move |event, _, control_flow| 
+    let mut occluded = false;
+    let mut focused = true;
+    let mut limiter = FrameLimiter::new();
+    let mut result = Ok(());
+    event_loop.run_return(|event, _, control_flow| 
     {
-        *control_flow = ControlFlow::Poll;
+        // Nothing is visible, so sleep until the window changes instead of spinning.
+        *control_flow = if minimized || occluded { ControlFlow::Wait } else { ControlFlow::Poll };
+
+        // Keys, buttons and the mouse are turned into actions for the next frame.
+        if let Event::WindowEvent { event, .. } = &event
+        {
+            app.input.handle_event(event);
+        }
+        let frame_ended = matches!(event, Event::MainEventsCleared);
+
         match event 
         {
             // Render a frame if our Vulkan app is not being destroyed.
-            Event::MainEventsCleared if !destroying && !minimized => unsafe { app.render(&window) }.unwrap(),
+            Event::MainEventsCleared if !destroying && !minimized && !occluded => 
+            {
+                limiter.wait(if focused { target_fps } else { background_fps });
+                result = unsafe { app.render(&window) };
+
+                // Headless style runs (e.g. CI) stop after a fixed number of frames.
+                let finished = exit_after_frames.is_some_and(|n| app.frame_count >= n);
+                if result.is_err() || finished
+                {
+                    destroying = true;
+                    *control_flow = ControlFlow::Exit;
+                    unsafe { app.destroy(); }
+                }
+
+                // Reported once the app is torn down, so the exit code reflects them.
+                let errors = app::debug_callback::error_count();
+                if result.is_ok() && finished && capture_validation && errors > 0
+                {
+                    result = Err(anyhow!("{} validation error(s) after {} frames.", errors, app.frame_count));
+                }
+            },
             // Resize window
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => 
             {
@@ -52,19 +100,8 @@ fn main() -> Result<()>
                     app.resized = true;
                 }
             },
-            // Keyboard Input
-            Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } =>
-            {
-                if input.state == ElementState::Pressed
-                {
-                    match input.virtual_keycode
-                    {
-                        Some(VirtualKeyCode::Left) if app.models > 1 => app.models -= 1,
-                        Some(VirtualKeyCode::Right) if app.models < 4 => app.models += 1,
-                        _ => { }
-                    }
-                }
-            },
+            Event::WindowEvent { event: WindowEvent::Focused(f), .. } => focused = f,
+            Event::WindowEvent { event: WindowEvent::Occluded(o), .. } => occluded = o,
             // Destroy our Vulkan app.
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => 
             {
@@ -74,5 +111,13 @@ fn main() -> Result<()>
             }
             _ => {}
         }
+
+        // Skipped and interrupted frames drop their input too, so a press is never seen twice.
+        if frame_ended
+        {
+            app.input.end_frame();
+        }
     });
-}
\ No newline at end of file
+
+    result
+}
@@ -0,0 +1,125 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long before a frame is due to stop sleeping and spin instead, since
+/// `thread::sleep` can overshoot by a scheduler tick.
+const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+/// Holds the event loop back to a target frame rate.
+#[derive(Copy, Clone, Debug)]
+pub struct FrameLimiter
+{
+    next_frame: Instant,
+}
+
+impl FrameLimiter
+{
+    pub fn new() -> Self
+    {
+        Self { next_frame: Instant::now() }
+    }
+
+    /// Blocks until the next frame is due at `fps`, or returns straight away
+    /// when there is no limit.
+    pub fn wait(&mut self, fps: Option<u32>)
+    {
+        let Some(deadline) = self.schedule(Instant::now(), fps) else { return };
+
+        if let Some(remaining) = deadline.checked_duration_since(Instant::now())
+        {
+            if remaining > SPIN_MARGIN
+            {
+                thread::sleep(remaining - SPIN_MARGIN);
+            }
+
+            while Instant::now() < deadline
+            {
+                std::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Returns when the frame started at `now` is due, and moves on to the next one.
+    fn schedule(&mut self, now: Instant, fps: Option<u32>) -> Option<Instant>
+    {
+        let interval = match fps.filter(|f| *f > 0)
+        {
+            Some(fps) => Duration::from_secs_f64(1.0 / fps as f64),
+            None =>
+            {
+                self.next_frame = now;
+                return None;
+            }
+        };
+
+        // After a stall or a lower limit, start over instead of catching up with a burst of frames.
+        if now > self.next_frame + interval
+        {
+            self.next_frame = now;
+        }
+
+        let deadline = self.next_frame;
+        self.next_frame += interval;
+
+        Some(deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn deadlines_advance_by_the_interval()
+    {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter { next_frame: start };
+
+        assert_eq!(limiter.schedule(start, Some(100)), Some(start));
+        assert_eq!(limiter.schedule(start + Duration::from_millis(3), Some(100)), Some(start + INTERVAL));
+        assert_eq!(limiter.schedule(start + INTERVAL, Some(100)), Some(start + INTERVAL * 2));
+    }
+
+    #[test]
+    fn a_late_frame_keeps_its_slot()
+    {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter { next_frame: start + INTERVAL };
+
+        // Less than a frame late, so the deadline has passed and the next one stays on the grid.
+        let now = start + Duration::from_millis(15);
+        assert_eq!(limiter.schedule(now, Some(100)), Some(start + INTERVAL));
+        assert_eq!(limiter.next_frame, start + INTERVAL * 2);
+    }
+
+    #[test]
+    fn deadlines_reset_after_a_stall()
+    {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter { next_frame: start };
+
+        let now = start + Duration::from_secs(1);
+        assert_eq!(limiter.schedule(now, Some(100)), Some(now));
+        assert_eq!(limiter.next_frame, now + INTERVAL);
+    }
+
+    #[test]
+    fn no_limit_never_waits()
+    {
+        let start = Instant::now();
+        let mut limiter = FrameLimiter { next_frame: start + Duration::from_secs(1) };
+
+        assert_eq!(limiter.schedule(start, None), None);
+        assert_eq!(limiter.schedule(start, Some(0)), None);
+        assert_eq!(limiter.next_frame, start);
+
+        // Returns without sleeping or spinning even though a deadline was pending.
+        let mut limiter = FrameLimiter { next_frame: Instant::now() + Duration::from_secs(60) };
+        let before = Instant::now();
+        limiter.wait(None);
+        assert!(before.elapsed() < Duration::from_secs(1));
+    }
+}